@@ -0,0 +1,1151 @@
+//! `aacs` command line tool for course authorities and students
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::str::FromStr;
+
+use aacs_client::{AssignmentAccounts, RetryPolicy};
+use anchor_client::anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_client::solana_sdk::{
+    commitment_config::CommitmentConfig, instruction::Instruction, pubkey::Pubkey,
+    signature::read_keypair_file, system_program, sysvar,
+};
+use anchor_client::{Client, Cluster, Program};
+use anchor_spl::{associated_token, token};
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Parser, Subcommand};
+use course_batch_manager::{
+    AllowList, AttemptPolicy, BatchSchedule, CheckerConfig, CheckerKind, EnrollmentPolicy,
+};
+
+#[derive(Parser)]
+#[clap(name = "aacs", about = "Automated assignment checker system")]
+struct Cli {
+    /// Path to the keypair of the course authority or the student
+    #[clap(long, global = true)]
+    keypair: Option<PathBuf>,
+    /// RPC URL or moniker (localnet, devnet, testnet, mainnet)
+    #[clap(long, global = true, default_value = "localnet")]
+    url: String,
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Manage courses
+    #[clap(subcommand)]
+    Course(CourseCommand),
+    /// Manage course batches
+    #[clap(subcommand)]
+    Batch(BatchCommand),
+    /// Manage and solve assignments
+    #[clap(subcommand)]
+    Assignment(AssignmentCommand),
+    /// Manage the cross-course reputation registry
+    #[clap(subcommand)]
+    Reputation(ReputationCommand),
+}
+
+#[derive(Subcommand)]
+enum CourseCommand {
+    /// Create new course owned by the keypair
+    Create {
+        /// Course id, up to 16 bytes
+        #[clap(long)]
+        course_id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum BatchCommand {
+    /// Create new course batch and its mint
+    Create {
+        #[clap(flatten)]
+        course: CourseArgs,
+        /// Course batch id, up to 16 bytes
+        #[clap(long)]
+        batch_id: String,
+        #[clap(flatten)]
+        schedule: ScheduleArgs,
+        #[clap(flatten)]
+        enrollment_policy: EnrollmentPolicyArgs,
+        /// Make batch tokens non-transferable
+        #[clap(long)]
+        soulbound: bool,
+    },
+    /// Create or refresh Metaplex metadata of the batch mint from the course metadata
+    SetMetadata {
+        #[clap(flatten)]
+        course: CourseArgs,
+        #[clap(long)]
+        batch_id: String,
+    },
+    /// Enroll the keypair into the course batch
+    Enroll {
+        #[clap(flatten)]
+        course: CourseArgs,
+        #[clap(long)]
+        batch_id: String,
+        /// Hex encoded Merkle proof nodes of the keypair allow-list leaf
+        #[clap(long)]
+        allow_list_proof: Vec<String>,
+        /// Create the cross-course reputation of the keypair unless it exists
+        #[clap(long)]
+        init_reputation: bool,
+    },
+    /// Leave the course batch, the batch token account is frozen
+    Withdraw {
+        #[clap(flatten)]
+        course: CourseArgs,
+        #[clap(long)]
+        batch_id: String,
+    },
+    /// Remove the student from the course batch
+    Expel {
+        #[clap(flatten)]
+        course: CourseArgs,
+        #[clap(long)]
+        batch_id: String,
+        #[clap(long)]
+        student: Pubkey,
+    },
+    /// Return withdrawn or expelled student to the course batch
+    Reinstate {
+        #[clap(flatten)]
+        course: CourseArgs,
+        #[clap(long)]
+        batch_id: String,
+        #[clap(long)]
+        student: Pubkey,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReputationCommand {
+    /// Create the registry of reputation writers, the keypair should be the program upgrade authority
+    InitConfig,
+    /// Allow the program to update reputations, course batch manager by default
+    AddWriter {
+        #[clap(long)]
+        program: Option<Pubkey>,
+    },
+}
+
+#[derive(Subcommand)]
+enum AssignmentCommand {
+    /// Create hash chain assignment checker with the ground truth answer
+    Create {
+        #[clap(flatten)]
+        course: CourseArgs,
+        /// Assignment id, up to 16 bytes
+        #[clap(long)]
+        assignment_id: String,
+        /// Limit the checker to the course batch, it is shared by all batches when omitted
+        #[clap(long)]
+        batch_id: Option<String>,
+        #[clap(flatten)]
+        checker: CheckerArgs,
+    },
+    /// Start solving the assignment
+    Start {
+        #[clap(flatten)]
+        course: CourseArgs,
+        #[clap(long)]
+        batch_id: String,
+        #[clap(long)]
+        assignment_id: String,
+    },
+    /// Submit the assignment solution, it is committed and revealed for commit-reveal checkers
+    Submit {
+        #[clap(flatten)]
+        course: CourseArgs,
+        #[clap(long)]
+        batch_id: String,
+        #[clap(long)]
+        assignment_id: String,
+        /// File with the solution
+        #[clap(long)]
+        answer_file: PathBuf,
+    },
+    /// Reject solutions until the assignment checker is resumed
+    Pause {
+        #[clap(flatten)]
+        course: CourseArgs,
+        #[clap(long)]
+        assignment_id: String,
+        /// Batch of the batch-scoped checker
+        #[clap(long)]
+        batch_id: Option<String>,
+    },
+    /// Accept solutions of the paused assignment checker again
+    Resume {
+        #[clap(flatten)]
+        course: CourseArgs,
+        #[clap(long)]
+        assignment_id: String,
+        /// Batch of the batch-scoped checker
+        #[clap(long)]
+        batch_id: Option<String>,
+    },
+    /// Close the assignment checker and get its rent back
+    Close {
+        #[clap(flatten)]
+        course: CourseArgs,
+        #[clap(long)]
+        assignment_id: String,
+        /// Batch of the batch-scoped checker
+        #[clap(long)]
+        batch_id: Option<String>,
+    },
+}
+
+/// Unix timestamps of the batch periods, the periods are unbounded when omitted
+#[derive(clap::Args)]
+struct ScheduleArgs {
+    #[clap(long)]
+    enrollment_opens_at: Option<i64>,
+    #[clap(long)]
+    enrollment_closes_at: Option<i64>,
+    #[clap(long)]
+    starts_at: Option<i64>,
+    #[clap(long)]
+    ends_at: Option<i64>,
+}
+
+impl From<&ScheduleArgs> for BatchSchedule {
+    fn from(args: &ScheduleArgs) -> Self {
+        BatchSchedule {
+            enrollment_opens_at: args.enrollment_opens_at.unwrap_or(0),
+            enrollment_closes_at: args.enrollment_closes_at.unwrap_or(i64::MAX),
+            starts_at: args.starts_at.unwrap_or(0),
+            ends_at: args.ends_at.unwrap_or(i64::MAX),
+        }
+    }
+}
+
+/// Enrollment is open to any number of students when omitted
+#[derive(clap::Args)]
+struct EnrollmentPolicyArgs {
+    #[clap(long)]
+    max_seats: Option<u32>,
+    /// Hex encoded Merkle root of the allowed students
+    #[clap(long, conflicts_with = "inviter")]
+    allow_list_root: Option<String>,
+    /// Pubkey signing invitations of the allowed students
+    #[clap(long)]
+    inviter: Option<Pubkey>,
+}
+
+impl EnrollmentPolicyArgs {
+    fn enrollment_policy(&self) -> Result<EnrollmentPolicy> {
+        let allow_list = match (&self.allow_list_root, self.inviter) {
+            (Some(root), _) => AllowList::MerkleRoot {
+                root: parse_hash("allow-list root", root)?,
+            },
+            (None, Some(inviter)) => AllowList::Invitations { inviter },
+            (None, None) => AllowList::Open,
+        };
+        Ok(EnrollmentPolicy {
+            max_seats: self.max_seats,
+            allow_list,
+        })
+    }
+}
+
+#[derive(clap::Args)]
+struct CourseArgs {
+    /// Course address, required after the course authority has been transferred
+    #[clap(long, conflicts_with_all = &["course-authority", "course-id"])]
+    course: Option<Pubkey>,
+    /// Course authority pubkey who created the course, the keypair pubkey when omitted
+    #[clap(long)]
+    course_authority: Option<Pubkey>,
+    /// Course id, up to 16 bytes
+    #[clap(long, required_unless_present = "course")]
+    course_id: Option<String>,
+}
+
+#[derive(clap::Args)]
+struct CheckerArgs {
+    /// File with the ground truth answer
+    #[clap(long)]
+    answer_file: PathBuf,
+    /// Max number of successful checks + 1
+    #[clap(long)]
+    hash_chain_length: u16,
+    /// Number of batch tokens minted on the first successful check
+    #[clap(long)]
+    reward: u16,
+    /// Hex encoded 32 bytes salt, random when omitted
+    #[clap(long)]
+    salt: Option<String>,
+    /// Max number of checks of each student, unlimited when omitted
+    #[clap(long)]
+    max_attempts: Option<u16>,
+    /// Min number of slots between two checks of a student
+    #[clap(long, default_value = "0")]
+    cooldown_slots: u64,
+    /// Accept solutions only through commit and reveal at least this number of slots apart
+    #[clap(long)]
+    reveal_delay_slots: Option<u64>,
+}
+
+impl CheckerArgs {
+    fn checker_config(
+        &self,
+        salt: [u8; 32],
+        ground_truth_hash_chain_tail: [u8; 32],
+    ) -> CheckerConfig {
+        CheckerConfig {
+            hash_chain_length: self.hash_chain_length,
+            to_mint_on_successful_check: self.reward,
+            salt,
+            ground_truth_hash_chain_tail,
+            reveal_delay_slots: self.reveal_delay_slots,
+            kind: CheckerKind::HashChain,
+            reward_schedule: None,
+            parts: vec![],
+            attempt_policy: AttemptPolicy {
+                max_attempts: self.max_attempts,
+                cooldown_slots: self.cooldown_slots,
+            },
+        }
+    }
+}
+
+impl CourseArgs {
+    /// The course address is derived from the key of the authority who created the course
+    fn course_pda(&self, payer: Pubkey) -> Result<Pubkey> {
+        if let Some(course) = self.course {
+            return Ok(course);
+        }
+        let course_id = self
+            .course_id
+            .as_deref()
+            .context("either --course or --course-id is required")?;
+        Ok(course_manager::course_canonical_pda(
+            self.course_authority.unwrap_or(payer),
+            &parse_id(course_id)?,
+        ))
+    }
+}
+
+/// Accounts of the student enrolled into the course batch
+struct EnrollmentAccounts {
+    course: Pubkey,
+    course_batch: Pubkey,
+    mint: Pubkey,
+    enrollment: Pubkey,
+    course_batch_token: Pubkey,
+}
+
+impl EnrollmentAccounts {
+    fn new(course: Pubkey, batch_id: &[u8; 16], student: Pubkey) -> Self {
+        let course_batch = course_batch_manager::batch_canonical_pda(course, batch_id);
+        let mint = course_batch_manager::batch_mint_canonical_pda(course, batch_id);
+        EnrollmentAccounts {
+            course,
+            course_batch,
+            mint,
+            enrollment: course_batch_manager::enrollment_canonical_pda(student, course_batch),
+            course_batch_token: associated_token::get_associated_token_address(&student, &mint),
+        }
+    }
+}
+
+impl fmt::Display for EnrollmentAccounts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "course: {}", self.course)?;
+        writeln!(f, "course batch: {}", self.course_batch)?;
+        writeln!(f, "enrollment: {}", self.enrollment)?;
+        writeln!(f, "course batch token: {}", self.course_batch_token)
+    }
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let keypair_path = match cli.keypair {
+        Some(path) => path,
+        None => default_keypair_path()?,
+    };
+    let payer = read_keypair_file(&keypair_path)
+        .map_err(|err| anyhow!("failed to read keypair {}: {}", keypair_path.display(), err))?;
+    let cluster = Cluster::from_str(&cli.url)?;
+    let client = Client::new_with_options(cluster, Rc::new(payer), CommitmentConfig::confirmed());
+
+    match cli.command {
+        Command::Course(CourseCommand::Create { course_id }) => {
+            let program = client.program(course_manager::ID);
+            create_course(&program, &course_id)
+        }
+        Command::Batch(command) => {
+            let program = client.program(course_batch_manager::ID);
+            match command {
+                BatchCommand::Create {
+                    course,
+                    batch_id,
+                    schedule,
+                    enrollment_policy,
+                    soulbound,
+                } => create_batch(
+                    &program,
+                    &course,
+                    &batch_id,
+                    (&schedule).into(),
+                    enrollment_policy.enrollment_policy()?,
+                    soulbound,
+                ),
+                BatchCommand::Enroll {
+                    course,
+                    batch_id,
+                    allow_list_proof,
+                    init_reputation,
+                } => enroll_batch(
+                    &program,
+                    &course,
+                    &batch_id,
+                    &allow_list_proof,
+                    init_reputation,
+                ),
+                BatchCommand::SetMetadata { course, batch_id } => {
+                    set_batch_token_metadata(&program, &course, &batch_id)
+                }
+                BatchCommand::Withdraw { course, batch_id } => {
+                    withdraw_from_batch(&program, &course, &batch_id)
+                }
+                BatchCommand::Expel {
+                    course,
+                    batch_id,
+                    student,
+                } => manage_enrollment(
+                    &program,
+                    &course,
+                    &batch_id,
+                    student,
+                    course_batch_manager::instruction::ExpelStudent {},
+                ),
+                BatchCommand::Reinstate {
+                    course,
+                    batch_id,
+                    student,
+                } => manage_enrollment(
+                    &program,
+                    &course,
+                    &batch_id,
+                    student,
+                    course_batch_manager::instruction::ReinstateStudent {},
+                ),
+            }
+        }
+        Command::Assignment(command) => {
+            let program = client.program(course_batch_manager::ID);
+            match command {
+                AssignmentCommand::Create {
+                    course,
+                    assignment_id,
+                    batch_id,
+                    checker,
+                } => create_assignment(
+                    &program,
+                    &course,
+                    &assignment_id,
+                    batch_id.as_deref(),
+                    &checker,
+                ),
+                AssignmentCommand::Start {
+                    course,
+                    batch_id,
+                    assignment_id,
+                } => start_assignment(&program, &course, &batch_id, &assignment_id),
+                AssignmentCommand::Submit {
+                    course,
+                    batch_id,
+                    assignment_id,
+                    answer_file,
+                } => submit_assignment(&program, &course, &batch_id, &assignment_id, &answer_file),
+                AssignmentCommand::Pause {
+                    course,
+                    assignment_id,
+                    batch_id,
+                } => manage_assignment_checker(
+                    &program,
+                    &course,
+                    &assignment_id,
+                    batch_id.as_deref(),
+                    course_batch_manager::instruction::PauseAssignmentChecker {},
+                ),
+                AssignmentCommand::Resume {
+                    course,
+                    assignment_id,
+                    batch_id,
+                } => manage_assignment_checker(
+                    &program,
+                    &course,
+                    &assignment_id,
+                    batch_id.as_deref(),
+                    course_batch_manager::instruction::ResumeAssignmentChecker {},
+                ),
+                AssignmentCommand::Close {
+                    course,
+                    assignment_id,
+                    batch_id,
+                } => close_assignment(&program, &course, &assignment_id, batch_id.as_deref()),
+            }
+        }
+        Command::Reputation(command) => {
+            let program = client.program(reputation::ID);
+            match command {
+                ReputationCommand::InitConfig => init_reputation_config(&program),
+                ReputationCommand::AddWriter { program: writer } => {
+                    add_reputation_writer(&program, writer.unwrap_or(course_batch_manager::ID))
+                }
+            }
+        }
+    }
+}
+
+fn create_course(program: &Program, course_id: &str) -> Result<()> {
+    let course_id = parse_id(course_id)?;
+    let course_authority = program.payer();
+    let course = course_manager::course_canonical_pda(course_authority, &course_id);
+    let course_metadata = course_manager::course_metadata_canonical_pda(course);
+    println!("course: {}", course);
+    println!("course metadata: {}", course_metadata);
+
+    program
+        .request()
+        .accounts(course_manager::accounts::NewCourse {
+            course_authority,
+            course,
+            course_metadata,
+            system_program: system_program::ID,
+        })
+        .args(course_manager::instruction::CreateNewCourse { course_id })
+        .send()?;
+    Ok(())
+}
+
+fn create_batch(
+    program: &Program,
+    course: &CourseArgs,
+    batch_id: &str,
+    schedule: BatchSchedule,
+    enrollment_policy: EnrollmentPolicy,
+    soulbound: bool,
+) -> Result<()> {
+    let batch_id = parse_id(batch_id)?;
+    let course = course.course_pda(program.payer())?;
+    let course_batch = course_batch_manager::batch_canonical_pda(course, &batch_id);
+    let mint = course_batch_manager::batch_mint_canonical_pda(course, &batch_id);
+    println!("course: {}", course);
+    println!("course batch: {}", course_batch);
+    println!("course batch mint: {}", mint);
+
+    program
+        .request()
+        .accounts(course_batch_manager::accounts::NewCourseBatch {
+            authority: program.payer(),
+            course,
+            course_metadata: course_manager::course_metadata_canonical_pda(course),
+            course_batch,
+            mint,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+            token_program: token::ID,
+        })
+        .args(course_batch_manager::instruction::CreateNewBatch {
+            batch_id,
+            schedule,
+            enrollment_policy,
+            soulbound,
+        })
+        .send()?;
+    Ok(())
+}
+
+fn enroll_batch(
+    program: &Program,
+    course_args: &CourseArgs,
+    batch_id: &str,
+    allow_list_proof: &[String],
+    init_reputation: bool,
+) -> Result<()> {
+    let batch_id = parse_id(batch_id)?;
+    let allow_list_proof = allow_list_proof
+        .iter()
+        .map(|node| parse_hash("allow-list proof node", node))
+        .collect::<Result<Vec<_>>>()?;
+    let student = program.payer();
+    let accounts = EnrollmentAccounts::new(course_args.course_pda(student)?, &batch_id, student);
+    print!("{}", accounts);
+
+    let mut request = program.request();
+    // the reputation is shared by all courses, the student creates it once
+    let reputation = reputation::reputation_canonical_pda(student);
+    let rpc = program.rpc();
+    if init_reputation
+        && rpc
+            .get_account_with_commitment(&reputation, rpc.commitment())
+            .context("failed to fetch the reputation")?
+            .value
+            .is_none()
+    {
+        println!("reputation: {}", reputation);
+        request = request.instruction(Instruction {
+            program_id: reputation::ID,
+            accounts: reputation::accounts::InitReputation {
+                payer: student,
+                reputation,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: reputation::instruction::InitReputation { student }.data(),
+        });
+    }
+    request
+        .accounts(course_batch_manager::accounts::EnrollBatch {
+            student,
+            course_batch: accounts.course_batch,
+            enrollment: accounts.enrollment,
+            mint: accounts.mint,
+            course_batch_token: accounts.course_batch_token,
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            rent: sysvar::rent::ID,
+            instructions: sysvar::instructions::ID,
+        })
+        .args(course_batch_manager::instruction::EnrollBatch { allow_list_proof })
+        .send()?;
+    Ok(())
+}
+
+fn init_reputation_config(program: &Program) -> Result<()> {
+    let config = reputation::config_canonical_pda();
+    println!("reputation config: {}", config);
+
+    program
+        .request()
+        .accounts(reputation::accounts::InitConfig {
+            authority: program.payer(),
+            config,
+            program: reputation::ID,
+            program_data: reputation::program_data_address(),
+            system_program: system_program::ID,
+        })
+        .args(reputation::instruction::InitConfig {})
+        .send()?;
+    Ok(())
+}
+
+fn add_reputation_writer(program: &Program, writer_program: Pubkey) -> Result<()> {
+    println!(
+        "reputation writer: {}",
+        reputation::writer_signer_address(&writer_program)
+    );
+
+    program
+        .request()
+        .accounts(reputation::accounts::UpdateConfig {
+            authority: program.payer(),
+            config: reputation::config_canonical_pda(),
+        })
+        .args(reputation::instruction::AddWriter { writer_program })
+        .send()?;
+    Ok(())
+}
+
+fn set_batch_token_metadata(program: &Program, course: &CourseArgs, batch_id: &str) -> Result<()> {
+    let batch_id = parse_id(batch_id)?;
+    let course = course.course_pda(program.payer())?;
+    let course_batch = course_batch_manager::batch_canonical_pda(course, &batch_id);
+    let mint = course_batch_manager::batch_mint_canonical_pda(course, &batch_id);
+    let metadata = Pubkey::find_program_address(
+        &[b"metadata", mpl_token_metadata::ID.as_ref(), mint.as_ref()],
+        &mpl_token_metadata::ID,
+    )
+    .0;
+    println!("course batch mint: {}", mint);
+    println!("token metadata: {}", metadata);
+
+    program
+        .request()
+        .accounts(course_batch_manager::accounts::SetBatchTokenMetadata {
+            authority: program.payer(),
+            course,
+            course_metadata: course_manager::course_metadata_canonical_pda(course),
+            course_batch,
+            mint,
+            metadata,
+            token_metadata_program: mpl_token_metadata::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        })
+        .args(course_batch_manager::instruction::SetBatchTokenMetadata {})
+        .send()?;
+    Ok(())
+}
+
+fn withdraw_from_batch(program: &Program, course: &CourseArgs, batch_id: &str) -> Result<()> {
+    let batch_id = parse_id(batch_id)?;
+    let student = program.payer();
+    let course = course.course_pda(student)?;
+    let course_batch = course_batch_manager::batch_canonical_pda(course, &batch_id);
+    let mint = course_batch_manager::batch_mint_canonical_pda(course, &batch_id);
+    let enrollment = course_batch_manager::enrollment_canonical_pda(student, course_batch);
+    println!("course batch: {}", course_batch);
+    println!("enrollment: {}", enrollment);
+
+    program
+        .request()
+        .accounts(course_batch_manager::accounts::WithdrawFromBatch {
+            student,
+            course_batch,
+            enrollment,
+            mint,
+            course_batch_token: associated_token::get_associated_token_address(&student, &mint),
+            token_program: token::ID,
+        })
+        .args(course_batch_manager::instruction::WithdrawFromBatch {})
+        .send()?;
+    Ok(())
+}
+
+/// Expel or reinstate the student depending on the instruction
+fn manage_enrollment(
+    program: &Program,
+    course: &CourseArgs,
+    batch_id: &str,
+    student: Pubkey,
+    instruction: impl InstructionData,
+) -> Result<()> {
+    let batch_id = parse_id(batch_id)?;
+    let course = course.course_pda(program.payer())?;
+    let course_batch = course_batch_manager::batch_canonical_pda(course, &batch_id);
+    let mint = course_batch_manager::batch_mint_canonical_pda(course, &batch_id);
+    let enrollment = course_batch_manager::enrollment_canonical_pda(student, course_batch);
+    println!("course batch: {}", course_batch);
+    println!("enrollment: {}", enrollment);
+
+    program
+        .request()
+        .accounts(course_batch_manager::accounts::ManageEnrollment {
+            authority: program.payer(),
+            course,
+            course_batch,
+            enrollment,
+            mint,
+            course_batch_token: associated_token::get_associated_token_address(&student, &mint),
+            token_program: token::ID,
+        })
+        .args(instruction)
+        .send()?;
+    Ok(())
+}
+
+fn create_assignment(
+    program: &Program,
+    course: &CourseArgs,
+    assignment_id: &str,
+    batch_id: Option<&str>,
+    checker: &CheckerArgs,
+) -> Result<()> {
+    if checker.hash_chain_length < 2 {
+        bail!("hash chain length should be at least 2");
+    }
+    let assignment_id = parse_id(assignment_id)?;
+    let salt = match &checker.salt {
+        Some(salt) => parse_salt(salt)?,
+        None => rand::random(),
+    };
+    let answer = read_answer(&checker.answer_file)?;
+    let ground_truth_hash_chain_tail =
+        aacs_client::hash_chain_tail(checker.hash_chain_length, &salt, &answer)?;
+    let course = course.course_pda(program.payer())?;
+    let scope = match batch_id {
+        Some(batch_id) => Some(course_batch_manager::batch_canonical_pda(
+            course,
+            &parse_id(batch_id)?,
+        )),
+        None => None,
+    };
+    let assignment_checker = match scope {
+        Some(course_batch) => course_batch_manager::batch_assignment_checker_canonical_pda(
+            course,
+            course_batch,
+            &assignment_id,
+        ),
+        None => course_batch_manager::assignment_checker_canonical_pda(course, &assignment_id),
+    };
+    println!("course: {}", course);
+    println!("assignment checker: {}", assignment_checker);
+
+    program
+        .request()
+        .accounts(course_batch_manager::accounts::CreateAssignmentChecker {
+            authority: program.payer(),
+            course,
+            assignment_checker,
+            assignment_checker_program: assignment_checker::ID,
+            course_batch_manager_program: course_batch_manager::ID,
+            system_program: system_program::ID,
+        })
+        .args(course_batch_manager::instruction::CreateAssignmentChecker {
+            assignment_id,
+            scope,
+            config: checker.checker_config(salt, ground_truth_hash_chain_tail),
+        })
+        .send()?;
+    Ok(())
+}
+
+/// Course and assignment checker addresses, the checker is batch-scoped when batch id is set
+fn assignment_checker_address(
+    program: &Program,
+    course: &CourseArgs,
+    assignment_id: &str,
+    batch_id: Option<&str>,
+) -> Result<(Pubkey, Pubkey)> {
+    let assignment_id = parse_id(assignment_id)?;
+    let course = course.course_pda(program.payer())?;
+    let assignment_checker = match batch_id {
+        Some(batch_id) => course_batch_manager::batch_assignment_checker_canonical_pda(
+            course,
+            course_batch_manager::batch_canonical_pda(course, &parse_id(batch_id)?),
+            &assignment_id,
+        ),
+        None => course_batch_manager::assignment_checker_canonical_pda(course, &assignment_id),
+    };
+    println!("assignment checker: {}", assignment_checker);
+    Ok((course, assignment_checker))
+}
+
+/// Pause or resume the assignment checker depending on the instruction
+fn manage_assignment_checker(
+    program: &Program,
+    course: &CourseArgs,
+    assignment_id: &str,
+    batch_id: Option<&str>,
+    instruction: impl InstructionData,
+) -> Result<()> {
+    let (course, assignment_checker) =
+        assignment_checker_address(program, course, assignment_id, batch_id)?;
+
+    program
+        .request()
+        .accounts(course_batch_manager::accounts::UpdateAssignmentChecker {
+            authority: program.payer(),
+            course,
+            assignment_checker,
+            assignment_checker_program: assignment_checker::ID,
+            course_batch_manager_program: course_batch_manager::ID,
+        })
+        .args(instruction)
+        .send()?;
+    Ok(())
+}
+
+fn close_assignment(
+    program: &Program,
+    course: &CourseArgs,
+    assignment_id: &str,
+    batch_id: Option<&str>,
+) -> Result<()> {
+    let (course, assignment_checker) =
+        assignment_checker_address(program, course, assignment_id, batch_id)?;
+    let checker_account: course_batch_manager::AssignmentCheckerState =
+        program.account(assignment_checker)?;
+    println!("rent payer: {}", checker_account.rent_payer);
+
+    program
+        .request()
+        .accounts(course_batch_manager::accounts::CloseAssignmentChecker {
+            authority: program.payer(),
+            course,
+            assignment_checker,
+            rent_payer: checker_account.rent_payer,
+            assignment_checker_program: assignment_checker::ID,
+            course_batch_manager_program: course_batch_manager::ID,
+        })
+        .args(course_batch_manager::instruction::CloseAssignmentChecker {})
+        .send()?;
+    Ok(())
+}
+
+fn start_assignment(
+    program: &Program,
+    course: &CourseArgs,
+    batch_id: &str,
+    assignment_id: &str,
+) -> Result<()> {
+    let batch_id = parse_id(batch_id)?;
+    let assignment_id = parse_id(assignment_id)?;
+    let student = program.payer();
+    let course = course.course_pda(student)?;
+    let course_batch = course_batch_manager::batch_canonical_pda(course, &batch_id);
+    let (_, scope) = find_assignment_checker(program, course, course_batch, &assignment_id)?;
+    let check_result = check_result_address(student, course, &assignment_id, scope);
+    println!("course: {}", course);
+    println!("check result: {}", check_result);
+
+    program
+        .request()
+        .accounts(course_batch_manager::accounts::CreateCheckResult {
+            student,
+            course,
+            course_batch,
+            enrollment: course_batch_manager::enrollment_canonical_pda(student, course_batch),
+            check_result,
+            assignment_checker_program: assignment_checker::ID,
+            course_batch_manager_program: course_batch_manager::ID,
+            system_program: system_program::ID,
+        })
+        .args(course_batch_manager::instruction::CreateCheckResult {
+            assignment_id,
+            scope,
+        })
+        .send()?;
+    Ok(())
+}
+
+fn submit_assignment(
+    program: &Program,
+    course: &CourseArgs,
+    batch_id: &str,
+    assignment_id: &str,
+    answer_file: &Path,
+) -> Result<()> {
+    let batch_id = parse_id(batch_id)?;
+    let assignment_id = parse_id(assignment_id)?;
+    let student = program.payer();
+    let course = course.course_pda(student)?;
+    let course_batch = course_batch_manager::batch_canonical_pda(course, &batch_id);
+    let (assignment_checker, scope) =
+        find_assignment_checker(program, course, course_batch, &assignment_id)?;
+    let accounts = AssignmentAccounts {
+        course,
+        course_batch,
+        mint: course_batch_manager::batch_mint_canonical_pda(course, &batch_id),
+        assignment_checker,
+    };
+    println!("course: {}", course);
+    println!("course batch: {}", accounts.course_batch);
+    println!("assignment checker: {}", accounts.assignment_checker);
+    println!(
+        "check result: {}",
+        check_result_address(student, course, &assignment_id, scope)
+    );
+
+    let answer = read_answer(answer_file)?;
+    let check_result =
+        aacs_client::check_assignment(program, &accounts, &answer, &RetryPolicy::default())?;
+    println!(
+        "check passed: {}, passed first time: {}",
+        check_result.check_passed, check_result.passed_first_time
+    );
+    Ok(())
+}
+
+/// Checker limited to the course batch when it exists, the course-wide checker otherwise
+///
+/// Returns the checker address and its scope
+fn find_assignment_checker(
+    program: &Program,
+    course: Pubkey,
+    course_batch: Pubkey,
+    assignment_id: &[u8; 16],
+) -> Result<(Pubkey, Option<Pubkey>)> {
+    let batch_checker = course_batch_manager::batch_assignment_checker_canonical_pda(
+        course,
+        course_batch,
+        assignment_id,
+    );
+    let rpc = program.rpc();
+    // fall back to the course-wide checker only when the batch one surely doesn't exist
+    let batch_checker_account = rpc
+        .get_account_with_commitment(&batch_checker, rpc.commitment())
+        .context("failed to fetch the batch assignment checker")?
+        .value;
+    Ok(match batch_checker_account {
+        Some(_) => (batch_checker, Some(course_batch)),
+        None => (
+            course_batch_manager::assignment_checker_canonical_pda(course, assignment_id),
+            None,
+        ),
+    })
+}
+
+fn check_result_address(
+    student: Pubkey,
+    course: Pubkey,
+    assignment_id: &[u8; 16],
+    scope: Option<Pubkey>,
+) -> Pubkey {
+    match scope {
+        Some(course_batch) => course_batch_manager::batch_check_result_canonical_pda(
+            student,
+            course,
+            course_batch,
+            assignment_id,
+        ),
+        None => course_batch_manager::check_result_canonical_pda(student, course, assignment_id),
+    }
+}
+
+fn default_keypair_path() -> Result<PathBuf> {
+    let home = std::env::var_os("HOME").context("HOME is not set, pass --keypair")?;
+    Ok(PathBuf::from(home).join(".config/solana/id.json"))
+}
+
+/// Course, batch and assignment ids are up to 16 bytes padded with `_`
+fn parse_id(id: &str) -> Result<[u8; 16]> {
+    if id.len() > 16 {
+        bail!("id {} is longer than 16 bytes", id);
+    }
+    let mut padded = [b'_'; 16];
+    padded[..id.len()].copy_from_slice(id.as_bytes());
+    Ok(padded)
+}
+
+fn parse_salt(salt: &str) -> Result<[u8; 32]> {
+    parse_hash("salt", salt)
+}
+
+/// 32 bytes value encoded with 64 hex digits
+fn parse_hash(name: &str, hex: &str) -> Result<[u8; 32]> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        bail!("{} should be 64 hex digits", name);
+    }
+    let mut bytes = [0; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16)
+            .with_context(|| format!("invalid hex {} {}", name, hex))?;
+    }
+    Ok(bytes)
+}
+
+/// Answer file content without the trailing line break
+fn read_answer(path: &Path) -> Result<Vec<u8>> {
+    let mut answer =
+        std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    while matches!(answer.last(), Some(b'\n' | b'\r')) {
+        answer.pop();
+    }
+    Ok(answer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(std::iter::once("aacs").chain(args.split_whitespace()))
+    }
+
+    fn course_args(args: &str) -> CourseArgs {
+        match parse(args).unwrap().command {
+            Command::Batch(BatchCommand::SetMetadata { course, .. }) => course,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn course_is_derived_from_course_id_and_authority() {
+        let payer = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let course_id = parse_id("web2_to_web3").unwrap();
+
+        let course = course_args("batch set-metadata --course-id web2_to_web3 --batch-id b");
+        assert_eq!(
+            course.course_pda(payer).unwrap(),
+            course_manager::course_canonical_pda(payer, &course_id)
+        );
+        let course = course_args(&format!(
+            "batch set-metadata --course-authority {} --course-id web2_to_web3 --batch-id b",
+            authority
+        ));
+        assert_eq!(
+            course.course_pda(payer).unwrap(),
+            course_manager::course_canonical_pda(authority, &course_id)
+        );
+        let address = Pubkey::new_unique();
+        let course = course_args(&format!(
+            "batch set-metadata --course {} --batch-id b",
+            address
+        ));
+        assert_eq!(course.course_pda(payer).unwrap(), address);
+    }
+
+    #[test]
+    fn course_address_excludes_course_id() {
+        let address = Pubkey::new_unique();
+        assert!(parse(&format!(
+            "batch set-metadata --course {} --course-id web2_to_web3 --batch-id b",
+            address
+        ))
+        .is_err());
+        assert!(parse("batch set-metadata --batch-id b").is_err());
+    }
+
+    #[test]
+    fn reputation_is_initialized_on_request() {
+        let init_reputation = |args: &str| match parse(args).unwrap().command {
+            Command::Batch(BatchCommand::Enroll {
+                init_reputation, ..
+            }) => init_reputation,
+            _ => unreachable!(),
+        };
+        assert!(!init_reputation("batch enroll --course-id c --batch-id b"));
+        assert!(init_reputation(
+            "batch enroll --course-id c --batch-id b --init-reputation"
+        ));
+    }
+
+    #[test]
+    fn checker_config_takes_reveal_delay() {
+        let checker = |args: &str| match parse(args).unwrap().command {
+            Command::Assignment(AssignmentCommand::Create { checker, .. }) => checker,
+            _ => unreachable!(),
+        };
+        let args = "assignment create --course-id c --assignment-id a --answer-file answer.txt \
+            --hash-chain-length 10 --reward 100";
+
+        let config = checker(args).checker_config([1; 32], [2; 32]);
+        assert_eq!(config.reveal_delay_slots, None);
+        assert_eq!(config.hash_chain_length, 10);
+        assert_eq!(config.to_mint_on_successful_check, 100);
+        assert!(config.kind == CheckerKind::HashChain);
+
+        let config = checker(&format!("{} --reveal-delay-slots 5 --max-attempts 3", args))
+            .checker_config([1; 32], [2; 32]);
+        assert_eq!(config.reveal_delay_slots, Some(5));
+        assert_eq!(config.attempt_policy.max_attempts, Some(3));
+        assert!(parse(&format!("{} --reveal-delay-slots soon", args)).is_err());
+    }
+
+    #[test]
+    fn enrollment_accounts_are_printed() {
+        let course = Pubkey::new_unique();
+        let student = Pubkey::new_unique();
+        let batch_id = parse_id("the_first_batch").unwrap();
+        let course_batch = course_batch_manager::batch_canonical_pda(course, &batch_id);
+        let mint = course_batch_manager::batch_mint_canonical_pda(course, &batch_id);
+        assert_eq!(
+            EnrollmentAccounts::new(course, &batch_id, student).to_string(),
+            format!(
+                "course: {}\ncourse batch: {}\nenrollment: {}\ncourse batch token: {}\n",
+                course,
+                course_batch,
+                course_batch_manager::enrollment_canonical_pda(student, course_batch),
+                associated_token::get_associated_token_address(&student, &mint),
+            )
+        );
+    }
+
+    #[test]
+    fn ids_are_padded_to_16_bytes() {
+        assert_eq!(&parse_id("space_hero").unwrap(), b"space_hero______");
+        assert!(parse_id("a_very_long_course_id").is_err());
+    }
+}
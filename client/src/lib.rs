@@ -0,0 +1,448 @@
+//! Client side helpers of the automated assignment checker system
+//!
+//! The payer of the `anchor_client::Program` acts as a student.
+use std::thread;
+use std::time::Duration;
+
+use anchor_client::solana_client::client_error::{
+    ClientError as SolanaClientError, ClientErrorKind as SolanaClientErrorKind,
+};
+use anchor_client::solana_client::rpc_request::{RpcError, RpcResponseErrorData};
+use anchor_client::solana_sdk::{
+    instruction::InstructionError, pubkey::Pubkey, signature::Signature, system_program, sysvar,
+    transaction::TransactionError,
+};
+use anchor_client::{ClientError, Program};
+use anchor_lang::solana_program::blake3;
+use anchor_spl::{associated_token::get_associated_token_address, token};
+use assignment_checker::AssignmentCheckerError;
+use course_batch_manager::{AssignmentCheckerState, CheckResult, Submission};
+
+/// Result of hash(...(hash(hashv([salt, value]))...)
+///
+/// hash is applied `hash_chain_length` number of times.
+/// Course authority passes it as the ground truth hash chain tail of a new checker.
+pub fn hash_chain_tail(
+    hash_chain_length: u16,
+    salt: &[u8; 32],
+    value: &[u8],
+) -> Result<[u8; 32], HashChainTooShort> {
+    if hash_chain_length < 1 {
+        return Err(HashChainTooShort(1));
+    }
+    let first_hash = blake3::hashv(&[salt, value]);
+    Ok((0..hash_chain_length - 1)
+        .fold(first_hash, |hash, _| blake3::hash(&hash.0))
+        .0)
+}
+
+/// The hash before current hash chain tail that a student submits to the checker
+pub fn hash_chain_tail_parent(
+    hash_chain_length: u16,
+    salt: &[u8; 32],
+    value: &[u8],
+) -> Result<[u8; 32], HashChainTooShort> {
+    if hash_chain_length < 2 {
+        return Err(HashChainTooShort(2));
+    }
+    hash_chain_tail(hash_chain_length - 1, salt, value)
+}
+
+/// The hash chain is shorter than the minimal length in hashes
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[error("the hash chain should have at least {0} hashes")]
+pub struct HashChainTooShort(pub u16);
+
+/// Delay between polls of the cluster slot while waiting for the reveal
+const SLOT_POLL_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Accounts a student needs to check an assignment of a course batch
+#[derive(Clone, Copy, Debug)]
+pub struct AssignmentAccounts {
+    pub course: Pubkey,
+    pub course_batch: Pubkey,
+    pub mint: Pubkey,
+    pub assignment_checker: Pubkey,
+}
+
+/// How `check_assignment` retries when the hash chain changes concurrently
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    /// Delay before the first retry, it is doubled on each next one
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(8),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CheckAssignmentError {
+    #[error("the hash chain of the assignment checker is fully used")]
+    HashChainExhausted,
+    #[error("the hash chain has changed during each of {0} retries")]
+    RetriesExhausted(u32),
+    #[error(transparent)]
+    Client(#[from] ClientError),
+}
+
+/// Check the assignment solution `value` with the hash chain checker
+///
+/// Reads the current `AssignmentCheckerState`, computes the hash chain tail parent
+/// from its salt and `value` and submits it. The check is retried with backoff
+/// when other students have cut the chain concurrently (`ExpectedHashLengthDiffers`).
+/// Checkers in commit-reveal submission mode get the hash through `send_commit_and_reveal`.
+/// Nothing is sent when the student has already passed the check.
+///
+/// Returns the check result of the student after the successful call.
+pub fn check_assignment(
+    program: &Program,
+    accounts: &AssignmentAccounts,
+    value: &[u8],
+    retry: &RetryPolicy,
+) -> Result<CheckResult, CheckAssignmentError> {
+    let checker: AssignmentCheckerState = program.account(accounts.assignment_checker)?;
+    let check_result: CheckResult =
+        program.account(check_result_address(program, accounts, &checker))?;
+    if check_result.check_passed {
+        return Ok(check_result);
+    }
+    let mut backoff = retry.initial_backoff;
+    for attempt in 0..=retry.max_retries {
+        let checker: AssignmentCheckerState = program.account(accounts.assignment_checker)?;
+        let parent = hash_chain_tail_parent(checker.hash_chain_length, &checker.salt, value)
+            .map_err(|_| CheckAssignmentError::HashChainExhausted)?;
+        let sent = match checker.reveal_delay_slots {
+            Some(_) => send_commit_and_reveal(program, accounts, &checker, parent),
+            None => send_check_assignment(
+                program,
+                accounts,
+                &checker,
+                Submission::HashChain {
+                    expected_hash_chain_length: checker.hash_chain_length,
+                    hash_chain_tail_parent: parent,
+                },
+            ),
+        };
+        match sent {
+            Ok(_) => return Ok(program.account(check_result_address(program, accounts, &checker))?),
+            Err(err) => match checker_error(&err) {
+                Some(AssignmentCheckerError::ZeroHashChainLength) => {
+                    return Err(CheckAssignmentError::HashChainExhausted)
+                }
+                Some(AssignmentCheckerError::ExpectedHashLengthDiffers)
+                    if attempt < retry.max_retries =>
+                {
+                    thread::sleep(backoff);
+                    backoff = (backoff * 2).min(retry.max_backoff);
+                }
+                Some(AssignmentCheckerError::ExpectedHashLengthDiffers) => {}
+                _ => return Err(err.into()),
+            },
+        }
+    }
+    Err(CheckAssignmentError::RetriesExhausted(retry.max_retries))
+}
+
+/// Send a single `check_assignment` instruction without retries
+///
+/// `checker` is the state of `accounts.assignment_checker`, its scope selects the check result
+pub fn send_check_assignment(
+    program: &Program,
+    accounts: &AssignmentAccounts,
+    checker: &AssignmentCheckerState,
+    submission: Submission,
+) -> Result<Signature, ClientError> {
+    program
+        .request()
+        .accounts(check_assignment_accounts(program, accounts, checker))
+        .args(course_batch_manager::instruction::CheckAssignment { submission })
+        .send()
+}
+
+/// Commit the hash chain tail parent, wait for the reveal delay of the checker and reveal it
+///
+/// `checker` is the state of `accounts.assignment_checker` in commit-reveal submission mode.
+/// The reveal fails with `ExpectedHashLengthDiffers` when the chain has been cut after the commit.
+pub fn send_commit_and_reveal(
+    program: &Program,
+    accounts: &AssignmentAccounts,
+    checker: &AssignmentCheckerState,
+    hash_chain_tail_parent: [u8; 32],
+) -> Result<Signature, ClientError> {
+    let student = program.payer();
+    let check_result = check_result_address(program, accounts, checker);
+    let nonce: [u8; 32] = rand::random();
+    program
+        .request()
+        .accounts(course_batch_manager::accounts::CommitAssignment {
+            student,
+            course: accounts.course,
+            assignment_checker: accounts.assignment_checker,
+            check_result,
+            assignment_checker_program: assignment_checker::ID,
+            course_batch_manager_program: course_batch_manager::ID,
+        })
+        .args(course_batch_manager::instruction::CommitAssignment {
+            commitment: assignment_checker::commitment_hash(
+                &student,
+                &hash_chain_tail_parent,
+                &nonce,
+            ),
+        })
+        .send()?;
+
+    let committed: CheckResult = program.account(check_result)?;
+    let reveal_slot = committed
+        .committed_at_slot
+        .saturating_add(checker.reveal_delay_slots.unwrap_or_default());
+    while program.rpc().get_slot()? < reveal_slot {
+        thread::sleep(SLOT_POLL_INTERVAL);
+    }
+    program
+        .request()
+        .accounts(check_assignment_accounts(program, accounts, checker))
+        .args(course_batch_manager::instruction::RevealAssignment {
+            expected_hash_chain_length: checker.hash_chain_length,
+            hash_chain_tail_parent,
+            nonce,
+        })
+        .send()
+}
+
+fn check_assignment_accounts(
+    program: &Program,
+    accounts: &AssignmentAccounts,
+    checker: &AssignmentCheckerState,
+) -> course_batch_manager::accounts::CheckAssignment {
+    let student = program.payer();
+    course_batch_manager::accounts::CheckAssignment {
+        student,
+        course: accounts.course,
+        course_batch: accounts.course_batch,
+        enrollment: course_batch_manager::enrollment_canonical_pda(student, accounts.course_batch),
+        assignment_checker: accounts.assignment_checker,
+        check_result: check_result_address(program, accounts, checker),
+        mint: accounts.mint,
+        course_batch_token: get_associated_token_address(&student, &accounts.mint),
+        system_program: system_program::ID,
+        token_program: token::ID,
+        assignment_checker_program: assignment_checker::ID,
+        course_batch_manager_program: course_batch_manager::ID,
+        instructions: sysvar::instructions::ID,
+        reputation_config: reputation::config_canonical_pda(),
+        reputation: reputation::reputation_canonical_pda(student),
+        reputation_writer: reputation::writer_signer_address(&course_batch_manager::ID),
+        reputation_program: reputation::ID,
+    }
+}
+
+fn check_result_address(
+    program: &Program,
+    accounts: &AssignmentAccounts,
+    checker: &AssignmentCheckerState,
+) -> Pubkey {
+    match checker.scope {
+        Some(course_batch) => course_batch_manager::batch_check_result_canonical_pda(
+            program.payer(),
+            accounts.course,
+            course_batch,
+            &checker.assignment_id,
+        ),
+        None => course_batch_manager::check_result_canonical_pda(
+            program.payer(),
+            accounts.course,
+            &checker.assignment_id,
+        ),
+    }
+}
+
+/// Hash chain error of the assignment checker returned by the failed transaction
+///
+/// Anchor numbers custom errors of each program from the same offset,
+/// so the error is taken from the assignment checker only when the logs of
+/// the transaction simulation show that this program has failed.
+pub fn checker_error(err: &ClientError) -> Option<AssignmentCheckerError> {
+    let err = match err {
+        ClientError::SolanaClientError(err) => err,
+        _ => return None,
+    };
+    let code = custom_error_code(err)?;
+    if failed_program_error(simulation_logs(err)?)? != (assignment_checker::ID, code) {
+        return None;
+    }
+    [
+        AssignmentCheckerError::ZeroHashChainLength,
+        AssignmentCheckerError::ExpectedHashLengthDiffers,
+    ]
+    .into_iter()
+    .find(|checker_error| u32::from(*checker_error) == code)
+}
+
+fn custom_error_code(err: &SolanaClientError) -> Option<u32> {
+    match err.get_transaction_error()? {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => Some(code),
+        _ => None,
+    }
+}
+
+fn simulation_logs(err: &SolanaClientError) -> Option<&[String]> {
+    match &err.kind {
+        SolanaClientErrorKind::RpcError(RpcError::RpcResponseError {
+            data: RpcResponseErrorData::SendTransactionPreflightFailure(simulation),
+            ..
+        }) => simulation.logs.as_deref(),
+        _ => None,
+    }
+}
+
+/// Program and custom error code of the first failed program, the innermost one of a CPI
+fn failed_program_error(logs: &[String]) -> Option<(Pubkey, u32)> {
+    let (program, error) = logs
+        .iter()
+        .find_map(|log| log.strip_prefix("Program ")?.split_once(" failed: "))?;
+    let code = error.strip_prefix("custom program error: 0x")?;
+    Some((program.parse().ok()?, u32::from_str_radix(code, 16).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_client::solana_client::rpc_response::RpcSimulateTransactionResult;
+
+    const SALT: [u8; 32] = [5; 32];
+
+    /// Preflight failure of a transaction with the logs of the failed program
+    fn preflight_failure(program: &Pubkey, code: u32) -> ClientError {
+        let logs = vec![
+            format!("Program {} invoke [1]", course_batch_manager::ID),
+            format!("Program {} invoke [2]", program),
+            format!(
+                "Program {} failed: custom program error: {:#x}",
+                program, code
+            ),
+            format!(
+                "Program {} failed: custom program error: {:#x}",
+                course_batch_manager::ID,
+                code
+            ),
+        ];
+        let err = TransactionError::InstructionError(0, InstructionError::Custom(code));
+        ClientError::SolanaClientError(SolanaClientError {
+            request: None,
+            kind: SolanaClientErrorKind::RpcError(RpcError::RpcResponseError {
+                code: -32002,
+                message: "Transaction simulation failed".to_string(),
+                data: RpcResponseErrorData::SendTransactionPreflightFailure(
+                    RpcSimulateTransactionResult {
+                        err: Some(err),
+                        logs: Some(logs),
+                        accounts: None,
+                        units_consumed: None,
+                    },
+                ),
+            }),
+        })
+    }
+
+    #[test]
+    fn checker_error_of_assignment_checker() {
+        for checker_error in [
+            AssignmentCheckerError::ZeroHashChainLength,
+            AssignmentCheckerError::ExpectedHashLengthDiffers,
+        ] {
+            let err = preflight_failure(&assignment_checker::ID, checker_error.into());
+            assert_eq!(
+                super::checker_error(&err).map(u32::from),
+                Some(checker_error.into())
+            );
+        }
+    }
+
+    #[test]
+    fn same_error_code_of_another_program_is_not_checker_error() {
+        let code = AssignmentCheckerError::ZeroHashChainLength.into();
+        for program in [course_batch_manager::ID, reputation::ID] {
+            assert!(checker_error(&preflight_failure(&program, code)).is_none());
+        }
+    }
+
+    #[test]
+    fn other_checker_errors_are_not_reported() {
+        let err = preflight_failure(
+            &assignment_checker::ID,
+            AssignmentCheckerError::CheckerPaused.into(),
+        );
+        assert!(checker_error(&err).is_none());
+    }
+
+    #[test]
+    fn error_without_logs_is_not_checker_error() {
+        let err = ClientError::SolanaClientError(SolanaClientError {
+            request: None,
+            kind: SolanaClientErrorKind::TransactionError(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(AssignmentCheckerError::ZeroHashChainLength.into()),
+            )),
+        });
+        assert!(checker_error(&err).is_none());
+        assert!(checker_error(&ClientError::AccountNotFound).is_none());
+    }
+
+    #[test]
+    fn hash_chain_tail_hashes_salted_value() {
+        let first_hash = blake3::hashv(&[&SALT, b"Gagarin"]).0;
+        assert_eq!(hash_chain_tail(1, &SALT, b"Gagarin").unwrap(), first_hash);
+        assert_eq!(
+            hash_chain_tail(3, &SALT, b"Gagarin").unwrap(),
+            blake3::hash(&blake3::hash(&first_hash).0).0
+        );
+        assert_ne!(
+            hash_chain_tail(3, &SALT, b"Gagarin").unwrap(),
+            hash_chain_tail(3, &[6; 32], b"Gagarin").unwrap()
+        );
+    }
+
+    #[test]
+    fn hash_chain_tail_parent_hashes_into_tail() {
+        for hash_chain_length in [2, 3, 10] {
+            let parent = hash_chain_tail_parent(hash_chain_length, &SALT, b"Gagarin").unwrap();
+            assert_eq!(
+                parent,
+                hash_chain_tail(hash_chain_length - 1, &SALT, b"Gagarin").unwrap()
+            );
+            assert_eq!(
+                blake3::hash(&parent).0,
+                hash_chain_tail(hash_chain_length, &SALT, b"Gagarin").unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn hash_chain_of_zero_length_has_no_tail() {
+        assert_eq!(
+            hash_chain_tail(0, &SALT, b"Gagarin"),
+            Err(HashChainTooShort(1))
+        );
+    }
+
+    #[test]
+    fn hash_chain_of_single_hash_has_no_tail_parent() {
+        assert_eq!(
+            hash_chain_tail_parent(1, &SALT, b"Gagarin"),
+            Err(HashChainTooShort(2))
+        );
+        assert_eq!(
+            hash_chain_tail_parent(0, &SALT, b"Gagarin"),
+            Err(HashChainTooShort(2))
+        );
+    }
+}
@@ -1,11 +1,102 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::blake3;
+use anchor_lang::solana_program::sysvar::instructions::{
+    load_current_index_checked, load_instruction_at_checked,
+};
+use anchor_lang::solana_program::{blake3, ed25519_program, sysvar};
 
 declare_id!("Po1RaS8BEDbNcn5oXsFryAeQ6Wn8fvmE111DJaKCgPC");
 
 pub const COURSE_DATA_SEED: &[u8; 11] = b"course_data";
 pub const ASSIGNMENT_ID_SEED: &[u8; 13] = b"assignment_id";
 pub const STUDENT_ADDRESS_SEED: &[u8; 15] = b"student_address";
+/// Maximal number of parts of `CheckerKind::MultiPart` checker
+pub const MAX_PARTS: usize = 16;
+/// Maximal number of questions of `CheckerKind::Quiz` checker
+pub const MAX_QUIZ_QUESTIONS: usize = 64;
+
+/// The last seed of checker and check result PDAs
+///
+/// Course-wide accounts have no scope, so their addresses don't change.
+/// Scoped accounts, like the ones of a single course batch, are derived with the scope pubkey.
+pub fn scope_seed(scope: &Option<Pubkey>) -> &[u8] {
+    match scope {
+        Some(scope) => scope.as_ref(),
+        None => &[],
+    }
+}
+
+/// Commitment to a hash chain tail parent made by a student before revealing it
+///
+/// Binding the student pubkey into the commitment makes it useless for anybody else
+pub fn commitment_hash(
+    student: &Pubkey,
+    hash_chain_tail_parent: &[u8; 32],
+    nonce: &[u8; 32],
+) -> [u8; 32] {
+    blake3::hashv(&[student.as_ref(), hash_chain_tail_parent, nonce]).0
+}
+
+/// Commitment to the answer key of `CheckerKind::Quiz` checker
+///
+/// The answer key has a choice per question. The secret nonce keeps short keys from being guessed
+/// until the course authority reveals it.
+pub fn answer_key_commitment(answer_key: &[u8], nonce: &[u8; 32]) -> [u8; 32] {
+    blake3::hashv(&[nonce, answer_key]).0
+}
+
+/// Commitment to the quiz choices made by a student before the quiz deadline
+pub fn quiz_commitment_hash(student: &Pubkey, choices: &[u8], nonce: &[u8; 32]) -> [u8; 32] {
+    blake3::hashv(&[student.as_ref(), nonce, choices]).0
+}
+
+/// Answer hash expected from a student by `CheckerKind::StudentBound` checker
+///
+/// The salt is mixed with the student pubkey, so the answer hash of one student
+/// is useless for another one
+pub fn student_bound_answer_hash(salt: &[u8; 32], student: &Pubkey, value: &[u8]) -> [u8; 32] {
+    blake3::hashv(&[salt, student.as_ref(), value]).0
+}
+
+/// Merkle tree leaf of a student answer hash
+///
+/// Leaves are public once the tree is shared, the answer hashes stay private
+pub fn student_bound_leaf(student: &Pubkey, answer_hash: &[u8; 32]) -> [u8; 32] {
+    blake3::hashv(&[student.as_ref(), answer_hash]).0
+}
+
+/// Hash of two sibling Merkle tree nodes
+///
+/// The nodes are sorted, so a proof doesn't need to carry the node positions
+pub fn merkle_node_hash(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    if a <= b {
+        blake3::hashv(&[a, b]).0
+    } else {
+        blake3::hashv(&[b, a]).0
+    }
+}
+
+/// Check whether the leaf with given proof hashes into the Merkle root
+pub fn verify_merkle_proof(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    let computed_root = proof
+        .iter()
+        .fold(leaf, |node, sibling| merkle_node_hash(&node, sibling));
+    computed_root == *root
+}
+
+/// Message signed by a grader of `CheckerKind::GraderAttestation` checker
+pub fn grader_attestation_message(
+    assignment_checker: &Pubkey,
+    student: &Pubkey,
+    score: u16,
+) -> Vec<u8> {
+    [
+        assignment_checker.as_ref(),
+        student.as_ref(),
+        &score.to_le_bytes(),
+    ]
+    .concat()
+}
+
 // Owner of AssignmentCheckerState and CheckResult accounts
 #[program]
 pub mod assignment_checker {
@@ -14,21 +105,24 @@ pub mod assignment_checker {
     pub fn init(
         ctx: Context<Init>,
         assignment_id: [u8; 16],
-        hash_chain_length: u16,
-        to_mint_on_successful_check: u16,
-        salt: [u8; 32],
-        // Creator of assignment checker is a trusted authority
-        // It should precompute ground truth hash chain tail
-        // to save nonfree compute operations of onchain program
-        // and not to send the ground truth assignment result value to public blockchain
-        ground_truth_hash_chain_tail: [u8; 32],
+        // Some(pubkey) limits the checker to the scope, e.g. a course batch
+        scope: Option<Pubkey>,
+        config: CheckerConfig,
     ) -> Result<()> {
+        config.validate()?;
         let checker_account = &mut ctx.accounts.assignment_checker;
         checker_account.assignment_id = assignment_id;
-        checker_account.hash_chain_length = hash_chain_length;
-        checker_account.to_mint_on_successful_check = to_mint_on_successful_check;
-        checker_account.salt = salt;
-        *checker_account.ground_truth_hash_chain_tail() = ground_truth_hash_chain_tail;
+        checker_account.scope = scope;
+        checker_account.hash_chain_length = config.hash_chain_length;
+        checker_account.to_mint_on_successful_check = config.to_mint_on_successful_check;
+        checker_account.salt = config.salt;
+        *checker_account.ground_truth_hash_chain_tail() = config.ground_truth_hash_chain_tail;
+        checker_account.reveal_delay_slots = config.reveal_delay_slots;
+        checker_account.kind = config.kind;
+        checker_account.reward_schedule = config.reward_schedule;
+        checker_account.parts = config.parts;
+        checker_account.attempt_policy = config.attempt_policy;
+        checker_account.rent_payer = ctx.accounts.authority.key();
         checker_account.bump_seed = *ctx
             .bumps
             .get("assignment_checker")
@@ -37,10 +131,126 @@ pub mod assignment_checker {
         Ok(())
     }
 
+    /// Replace the Merkle root of a `CheckerKind::StudentBound` checker
+    ///
+    /// The course authority rebuilds the tree when students join after the checker is created.
+    /// Results of the students who have already passed are kept.
+    pub fn set_answers_root(ctx: Context<UpdateChecker>, answers_root: [u8; 32]) -> Result<()> {
+        let checker_account = &mut ctx.accounts.assignment_checker;
+        match &mut checker_account.kind {
+            CheckerKind::StudentBound {
+                answers_root: current_root,
+            } => *current_root = answers_root,
+            _ => return Err(error!(AssignmentCheckerError::UnsupportedCheckerKind)),
+        }
+        msg!("answers root is updated for {}", checker_account.key());
+        Ok(())
+    }
+
+    /// Install a new hash chain into a `CheckerKind::HashChain` checker
+    ///
+    /// Used when the hash chain is exhausted or about to be.
+    /// `hash_chain_generation` is incremented, so clients can tell which chain they hash against.
+    /// Existing check results are kept.
+    pub fn refill_hash_chain(
+        ctx: Context<UpdateChecker>,
+        hash_chain_length: u16,
+        salt: [u8; 32],
+        ground_truth_hash_chain_tail: [u8; 32],
+    ) -> Result<()> {
+        let checker_account = &mut ctx.accounts.assignment_checker;
+        if checker_account.kind != CheckerKind::HashChain {
+            return Err(error!(AssignmentCheckerError::UnsupportedCheckerKind));
+        }
+        if hash_chain_length < 2 {
+            return Err(error!(AssignmentCheckerError::HashChainTooShort));
+        }
+        checker_account.hash_chain_length = hash_chain_length;
+        checker_account.salt = salt;
+        *checker_account.ground_truth_hash_chain_tail() = ground_truth_hash_chain_tail;
+        checker_account.hash_chain_generation += 1;
+        msg!(
+            "hash chain generation {} is installed for {}",
+            checker_account.hash_chain_generation,
+            checker_account.key()
+        );
+        Ok(())
+    }
+
+    /// Reveal the answer key of `CheckerKind::Quiz` checker after the quiz deadline
+    ///
+    /// The answer key should match the commitment of the checker.
+    /// Students grade their committed choices with `grade_quiz` after the reveal.
+    pub fn reveal_answer_key(
+        ctx: Context<UpdateChecker>,
+        answer_key: Vec<u8>,
+        nonce: [u8; 32],
+    ) -> Result<()> {
+        let checker_account = &mut ctx.accounts.assignment_checker;
+        let (commitment, question_count) = match checker_account.kind {
+            CheckerKind::Quiz {
+                answer_key_commitment,
+                question_count,
+            } => (answer_key_commitment, question_count),
+            _ => return Err(error!(AssignmentCheckerError::UnsupportedCheckerKind)),
+        };
+        if !checker_account.answer_key.is_empty() {
+            return Err(error!(AssignmentCheckerError::AnswerKeyAlreadyRevealed));
+        }
+        let cutoff_at = checker_account
+            .reward_schedule
+            .and_then(|schedule| schedule.cutoff_at)
+            .ok_or_else(|| error!(AssignmentCheckerError::InvalidQuiz))?;
+        if Clock::get()?.unix_timestamp < cutoff_at {
+            return Err(error!(AssignmentCheckerError::QuizNotClosed));
+        }
+        if answer_key.len() != usize::from(question_count)
+            || answer_key_commitment(&answer_key, &nonce) != commitment
+        {
+            return Err(error!(AssignmentCheckerError::CommitmentMismatch));
+        }
+        checker_account.answer_key = answer_key;
+        msg!("answer key is revealed for {}", checker_account.key());
+        Ok(())
+    }
+
+    /// Pause or resume the checker
+    ///
+    /// Solutions for a paused checker are rejected, check results are kept.
+    pub fn set_paused(ctx: Context<UpdateChecker>, paused: bool) -> Result<()> {
+        let checker_account = &mut ctx.accounts.assignment_checker;
+        checker_account.paused = paused;
+        msg!(
+            "assignment checker {} is {}",
+            checker_account.key(),
+            if paused { "paused" } else { "resumed" }
+        );
+        Ok(())
+    }
+
+    /// Close the checker and return its lamports to the instructor who created it
+    ///
+    /// Check results of students are kept, so passed assignments aren't rewarded twice
+    /// if a checker with the same assignment id is created again.
+    pub fn close(ctx: Context<CloseChecker>) -> Result<()> {
+        msg!(
+            "assignment checker {} is closed",
+            ctx.accounts.assignment_checker.key()
+        );
+        Ok(())
+    }
+
     /// Init check result created by the result_processor_program
-    pub fn init_check_result(ctx: Context<InitCheckResult>, assignment_id: [u8; 16]) -> Result<()> {
+    ///
+    /// The scope should be the same as the scope of the assignment checker
+    pub fn init_check_result(
+        ctx: Context<InitCheckResult>,
+        assignment_id: [u8; 16],
+        scope: Option<Pubkey>,
+    ) -> Result<()> {
         let check_result = &mut ctx.accounts.check_result;
         check_result.assignment_id = assignment_id;
+        check_result.scope = scope;
         check_result.bump_seed = *ctx
             .bumps
             .get("check_result")
@@ -58,54 +268,355 @@ pub mod assignment_checker {
     ///     different hash chain length than the checker currently has.  This
     ///     can happen during concurrent checks by multiple students and should
     ///     be mitigated by retry with actual hash chain length
-    pub fn check(
+    ///     * Returns `AssignmentChecker::CommitRevealRequired` when the checker
+    ///     accepts solutions only through `commit` and `reveal`
+    ///     * Returns `AssignmentChecker::SubmissionKindMismatch` when the submission
+    ///     doesn't match the checker kind
+    ///     * Returns `AssignmentChecker::InvalidAttestation` when the instruction before
+    ///     the current one doesn't verify grader signature of the attested score
+    ///     or the score is above the max score of the grader
+    ///     * Returns `AssignmentChecker::SubmissionCutoff` after the hard cutoff of the checker
+    ///     * Returns `AssignmentChecker::InvalidPartIndex` when a submitted part
+    ///     doesn't exist in the multi-part checker
+    ///     * Returns `AssignmentChecker::TooManyAttempts` when the student has used
+    ///     all attempts of the checker
+    ///     * Returns `AssignmentChecker::AttemptCooldown` when the cooldown of the checker
+    ///     hasn't passed since the previous attempt
+    ///     * Returns `AssignmentChecker::CheckerPaused` while the checker is paused
+    pub fn check(ctx: Context<Check>, submission: Submission) -> Result<()> {
+        ctx.accounts
+            .assignment_checker
+            .require_accepting_solutions()?;
+        let attempt_policy = ctx.accounts.assignment_checker.attempt_policy;
+        ctx.accounts.check_result.record_attempt(&attempt_policy)?;
+        let student_key = ctx.accounts.student.key();
+        let checker_key = ctx.accounts.assignment_checker.key();
+        let checker_account = &mut ctx.accounts.assignment_checker;
+        let check_result_account = &mut ctx.accounts.check_result;
+        match (checker_account.kind, submission) {
+            (
+                CheckerKind::HashChain,
+                Submission::HashChain {
+                    expected_hash_chain_length,
+                    hash_chain_tail_parent,
+                },
+            ) => {
+                if checker_account.reveal_delay_slots.is_some() {
+                    return Err(error!(AssignmentCheckerError::CommitRevealRequired));
+                }
+                check_hash_chain(
+                    checker_account,
+                    check_result_account,
+                    expected_hash_chain_length,
+                    hash_chain_tail_parent,
+                )
+            }
+            (
+                CheckerKind::StudentBound { answers_root },
+                Submission::StudentBound { answer_hash, proof },
+            ) => {
+                let leaf = student_bound_leaf(&student_key, &answer_hash);
+                check_answer(
+                    check_result_account,
+                    verify_merkle_proof(&proof, &answers_root, leaf),
+                );
+                Ok(())
+            }
+            (
+                CheckerKind::GraderAttestation {
+                    grader,
+                    min_score,
+                    max_score,
+                },
+                Submission::GraderAttestation { score },
+            ) => {
+                if score > max_score
+                    || !is_signed_in_previous_instruction(
+                        &ctx.accounts.instructions,
+                        &grader,
+                        &grader_attestation_message(&checker_key, &student_key, score),
+                    )?
+                {
+                    return Err(error!(AssignmentCheckerError::InvalidAttestation));
+                }
+                check_result_account.score = check_result_account.score.max(score);
+                check_answer(check_result_account, score >= min_score);
+                Ok(())
+            }
+            (CheckerKind::MultiPart, Submission::MultiPart { parts }) => {
+                check_parts(checker_account, check_result_account, &parts)
+            }
+            _ => Err(error!(AssignmentCheckerError::SubmissionKindMismatch)),
+        }?;
+        ctx.accounts.check_result.record_first_pass()
+    }
+
+    /// Save student's commitment to a hash chain tail parent or to quiz choices
+    ///
+    /// The commitment is `commitment_hash(student, hash_chain_tail_parent, nonce)`
+    /// or `quiz_commitment_hash(student, choices, nonce)` for quiz checkers.
+    /// It hides the solution hash from observers of pending transactions
+    /// until the student reveals it. A new commitment replaces the previous one.
+    pub fn commit(ctx: Context<Commit>, commitment: [u8; 32]) -> Result<()> {
+        let checker_account = &ctx.accounts.assignment_checker;
+        checker_account.require_accepting_solutions()?;
+        match checker_account.kind {
+            CheckerKind::HashChain if checker_account.reveal_delay_slots.is_none() => {
+                return Err(error!(AssignmentCheckerError::CommitRevealDisabled))
+            }
+            CheckerKind::HashChain | CheckerKind::Quiz { .. } => {}
+            _ => return Err(error!(AssignmentCheckerError::SubmissionKindMismatch)),
+        }
+        let check_result_account = &mut ctx.accounts.check_result;
+        check_result_account.commitment = Some(commitment);
+        check_result_account.committed_at_slot = Clock::get()?.slot;
+        msg!("commitment is saved");
+        Ok(())
+    }
+
+    /// Reveal the committed hash chain tail parent and check it
+    ///
+    /// Errors (in addition to the errors of `check`):
+    ///     * Returns `AssignmentChecker::NoCommitment` when the student hasn't committed
+    ///     * Returns `AssignmentChecker::RevealTooEarly` when less than
+    ///     `reveal_delay_slots` slots have passed since the commit
+    ///     * Returns `AssignmentChecker::CommitmentMismatch` when the revealed values
+    ///     don't hash into the commitment
+    pub fn reveal(
         ctx: Context<Check>,
-        // used to validate the hash chain length
-        // that the client expects and deal with concurrent checks
         expected_hash_chain_length: u16,
-        // the hash before current hash chain tail
         hash_chain_tail_parent: [u8; 32],
+        nonce: [u8; 32],
     ) -> Result<()> {
+        if ctx.accounts.assignment_checker.kind != CheckerKind::HashChain {
+            return Err(error!(AssignmentCheckerError::SubmissionKindMismatch));
+        }
+        ctx.accounts
+            .assignment_checker
+            .require_accepting_solutions()?;
+        let attempt_policy = ctx.accounts.assignment_checker.attempt_policy;
+        ctx.accounts.check_result.record_attempt(&attempt_policy)?;
+        let reveal_delay_slots = ctx
+            .accounts
+            .assignment_checker
+            .reveal_delay_slots
+            .ok_or_else(|| error!(AssignmentCheckerError::CommitRevealDisabled))?;
+
         let check_result_account = &mut ctx.accounts.check_result;
-        if check_result_account.check_passed {
-            // previous check succeded
-            // This check is no longer the first
-            check_result_account.passed_first_time = false;
-        } else {
-            // this check hasn't passed yet
-            let checker_account = &mut ctx.accounts.assignment_checker;
-            if checker_account.hash_chain_length == 0 {
-                // checker has used full hash chain
-                return Err(error!(AssignmentCheckerError::ZeroHashChainLength));
-            }
+        let commitment = check_result_account
+            .commitment
+            .take()
+            .ok_or_else(|| error!(AssignmentCheckerError::NoCommitment))?;
+        let reveal_slot = check_result_account
+            .committed_at_slot
+            .saturating_add(reveal_delay_slots);
+        if Clock::get()?.slot < reveal_slot {
+            return Err(error!(AssignmentCheckerError::RevealTooEarly));
+        }
+        if commitment
+            != commitment_hash(&ctx.accounts.student.key(), &hash_chain_tail_parent, &nonce)
+        {
+            return Err(error!(AssignmentCheckerError::CommitmentMismatch));
+        }
 
-            if checker_account.hash_chain_length != expected_hash_chain_length {
-                // client expects different hash chain length then the checker has at the moment
-                return Err(error!(AssignmentCheckerError::ExpectedHashLengthDiffers));
-            }
+        check_hash_chain(
+            &mut ctx.accounts.assignment_checker,
+            &mut ctx.accounts.check_result,
+            expected_hash_chain_length,
+            hash_chain_tail_parent,
+        )?;
+        ctx.accounts.check_result.record_first_pass()
+    }
 
-            let tail_hash = blake3::hash(&hash_chain_tail_parent);
-            if tail_hash == blake3::Hash(checker_account.ground_truth_hash_chain_tail) {
-                // check has passed the first time
-                check_result_account.check_passed = true;
-                check_result_account.passed_first_time = true;
-                // remove tail from the chain
-                checker_account.hash_chain_length -= 1;
-                checker_account.ground_truth_hash_chain_tail = hash_chain_tail_parent;
-                msg!("check is passed");
-            }
-            // else: keep check_passed and passed_first_time as false
+    /// Score the committed quiz choices against the revealed answer key
+    ///
+    /// The score is the number of correct choices, the check passes when it is positive.
+    /// The commitment is consumed, so a quiz is graded once.
+    ///
+    /// Errors:
+    ///     * Returns `AssignmentChecker::AnswerKeyNotRevealed` before the course authority
+    ///     reveals the answer key
+    ///     * Returns `AssignmentChecker::NoCommitment` when the student hasn't committed
+    ///     or the quiz is already graded
+    ///     * Returns `AssignmentChecker::CommitmentMismatch` when the choices
+    ///     don't hash into the commitment
+    ///     * Returns `AssignmentChecker::CheckerPaused` while the checker is paused
+    pub fn grade_quiz(ctx: Context<Check>, choices: Vec<u8>, nonce: [u8; 32]) -> Result<()> {
+        let checker_account = &ctx.accounts.assignment_checker;
+        checker_account.require_active()?;
+        if !matches!(checker_account.kind, CheckerKind::Quiz { .. }) {
+            return Err(error!(AssignmentCheckerError::SubmissionKindMismatch));
         }
-        Ok(())
+        if checker_account.answer_key.is_empty() {
+            return Err(error!(AssignmentCheckerError::AnswerKeyNotRevealed));
+        }
+
+        let check_result_account = &mut ctx.accounts.check_result;
+        let commitment = check_result_account
+            .commitment
+            .take()
+            .ok_or_else(|| error!(AssignmentCheckerError::NoCommitment))?;
+        if commitment != quiz_commitment_hash(&ctx.accounts.student.key(), &choices, &nonce) {
+            return Err(error!(AssignmentCheckerError::CommitmentMismatch));
+        }
+        let score = checker_account
+            .answer_key
+            .iter()
+            .zip(&choices)
+            .filter(|(answer, choice)| answer == choice)
+            .count() as u16;
+        check_result_account.score = score;
+        msg!("quiz score is {}", score);
+        check_answer(check_result_account, score > 0);
+        check_result_account.record_first_pass()
+    }
+}
+
+fn check_hash_chain(
+    checker_account: &mut AssignmentCheckerState,
+    check_result_account: &mut CheckResult,
+    expected_hash_chain_length: u16,
+    hash_chain_tail_parent: [u8; 32],
+) -> Result<()> {
+    if check_result_account.check_passed {
+        // previous check succeded
+        // This check is no longer the first
+        check_result_account.passed_first_time = false;
+    } else {
+        // this check hasn't passed yet
+        if checker_account.hash_chain_length == 0 {
+            // checker has used full hash chain
+            return Err(error!(AssignmentCheckerError::ZeroHashChainLength));
+        }
+
+        if checker_account.hash_chain_length != expected_hash_chain_length {
+            // client expects different hash chain length then the checker has at the moment
+            return Err(error!(AssignmentCheckerError::ExpectedHashLengthDiffers));
+        }
+
+        let tail_hash = blake3::hash(&hash_chain_tail_parent);
+        if tail_hash == blake3::Hash(checker_account.ground_truth_hash_chain_tail) {
+            // check has passed the first time
+            check_result_account.check_passed = true;
+            check_result_account.passed_first_time = true;
+            // remove tail from the chain
+            checker_account.hash_chain_length -= 1;
+            checker_account.ground_truth_hash_chain_tail = hash_chain_tail_parent;
+            msg!("check is passed");
+        }
+        // else: keep check_passed and passed_first_time as false
+    }
+    Ok(())
+}
+
+/// Check submitted parts of a multi-part assignment against their hash chains
+///
+/// Parts which have passed before are skipped without cutting their chains.
+/// The whole check passes when all parts have passed.
+fn check_parts(
+    checker_account: &mut AssignmentCheckerState,
+    check_result_account: &mut CheckResult,
+    submissions: &[PartSubmission],
+) -> Result<()> {
+    let was_passed = check_result_account.check_passed;
+    for submission in submissions {
+        let part = checker_account
+            .parts
+            .get_mut(usize::from(submission.index))
+            .ok_or_else(|| error!(AssignmentCheckerError::InvalidPartIndex))?;
+        let part_bit = 1 << submission.index;
+        if check_result_account.parts_passed & part_bit != 0 {
+            continue;
+        }
+        if part.hash_chain_length == 0 {
+            return Err(error!(AssignmentCheckerError::ZeroHashChainLength));
+        }
+        if part.hash_chain_length != submission.expected_hash_chain_length {
+            return Err(error!(AssignmentCheckerError::ExpectedHashLengthDiffers));
+        }
+        let tail_hash = blake3::hash(&submission.hash_chain_tail_parent);
+        if tail_hash == blake3::Hash(part.ground_truth_hash_chain_tail) {
+            check_result_account.parts_passed |= part_bit;
+            // remove tail from the chain of the part
+            part.hash_chain_length -= 1;
+            part.ground_truth_hash_chain_tail = submission.hash_chain_tail_parent;
+            msg!("part {} is passed", submission.index);
+        }
+    }
+    check_result_account.check_passed =
+        check_result_account.parts_passed == checker_account.all_parts();
+    check_result_account.passed_first_time = check_result_account.check_passed && !was_passed;
+    Ok(())
+}
+
+/// Whether the previous instruction of the transaction is an ed25519 program instruction
+/// verifying the `signer` signature of the message
+///
+/// The ed25519 program fails the whole transaction when the signature is wrong,
+/// so it's enough to check which key and message the instruction verifies.
+pub fn is_signed_in_previous_instruction(
+    instructions: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<bool> {
+    // Ed25519 instruction data layout:
+    // number of signatures (u8), padding (u8) and offsets of the signature (7 x u16)
+    const OFFSETS_START: usize = 2;
+    const OFFSETS_END: usize = OFFSETS_START + 14;
+    // the data is expected in the ed25519 instruction itself
+    const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+    let current_index = load_current_index_checked(instructions)?;
+    if current_index == 0 {
+        return Ok(false);
+    }
+    let ed25519_instruction =
+        load_instruction_at_checked((current_index - 1).into(), instructions)?;
+    let data = &ed25519_instruction.data;
+    if ed25519_instruction.program_id != ed25519_program::ID
+        || data.len() < OFFSETS_END
+        || data[0] != 1
+    {
+        return Ok(false);
+    }
+
+    let read_u16 = |offset: usize| u16::from_le_bytes([data[offset], data[offset + 1]]);
+    let signature_instruction_index = read_u16(OFFSETS_START + 2);
+    let public_key_offset = usize::from(read_u16(OFFSETS_START + 4));
+    let public_key_instruction_index = read_u16(OFFSETS_START + 6);
+    let message_offset = usize::from(read_u16(OFFSETS_START + 8));
+    let message_size = usize::from(read_u16(OFFSETS_START + 10));
+    let message_instruction_index = read_u16(OFFSETS_START + 12);
+    if signature_instruction_index != CURRENT_INSTRUCTION
+        || public_key_instruction_index != CURRENT_INSTRUCTION
+        || message_instruction_index != CURRENT_INSTRUCTION
+    {
+        return Ok(false);
+    }
+
+    let signed_public_key = data.get(public_key_offset..public_key_offset + 32);
+    let signed_message = data.get(message_offset..message_offset + message_size);
+    Ok(signed_public_key == Some(signer.as_ref()) && signed_message == Some(message))
+}
+
+/// Save result of a check which doesn't consume checker state
+fn check_answer(check_result_account: &mut CheckResult, answer_is_correct: bool) {
+    if check_result_account.check_passed {
+        // This check is no longer the first
+        check_result_account.passed_first_time = false;
+    } else if answer_is_correct {
+        check_result_account.check_passed = true;
+        check_result_account.passed_first_time = true;
+        msg!("check is passed");
     }
 }
 
 #[derive(Accounts)]
-#[instruction(assignment_id: [u8; 16], hash_chain_length: u16)]
+#[instruction(assignment_id: [u8; 16], scope: Option<Pubkey>)]
 pub struct Init<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
-    #[account(has_one = authority)]
+    #[account(constraint = course.is_instructor(&authority.key()) @ AssignmentCheckerError::NotCourseInstructor)]
     pub course: Account<'info, course_manager::Course>,
 
     #[account(
@@ -116,15 +627,63 @@ pub struct Init<'info> {
         course.key().as_ref(),
         ASSIGNMENT_ID_SEED,
         assignment_id.as_ref(),
+        scope_seed(&scope),
     ],
-    seeds::program = result_processor_program, bump, constraint = hash_chain_length >= 2)]
+    seeds::program = result_processor_program, bump)]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+    #[account(executable)]
+    pub result_processor_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateChecker<'info> {
+    pub authority: Signer<'info>,
+    #[account(constraint = course.authority == authority.key() @ AssignmentCheckerError::NotCourseAuthority)]
+    pub course: Account<'info, course_manager::Course>,
+
+    #[account(mut,
+        signer,
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        assignment_checker.assignment_id.as_ref(),
+        scope_seed(&assignment_checker.scope),
+    ], seeds::program = result_processor_program, bump=assignment_checker.bump_seed,
+    )]
     pub assignment_checker: Account<'info, AssignmentCheckerState>,
     #[account(executable)]
     pub result_processor_program: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
-#[instruction(assignment_id: [u8; 16])]
+pub struct CloseChecker<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(constraint = course.authority == authority.key() @ AssignmentCheckerError::NotCourseAuthority)]
+    pub course: Account<'info, course_manager::Course>,
+
+    #[account(mut,
+        signer,
+        close = rent_payer,
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        assignment_checker.assignment_id.as_ref(),
+        scope_seed(&assignment_checker.scope),
+    ], seeds::program = result_processor_program, bump=assignment_checker.bump_seed,
+    )]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+    /// CHECK: the address is saved on the checker when it is created
+    #[account(mut, address = assignment_checker.rent_payer)]
+    pub rent_payer: AccountInfo<'info>,
+    #[account(executable)]
+    pub result_processor_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(assignment_id: [u8; 16], scope: Option<Pubkey>)]
 pub struct InitCheckResult<'info> {
     #[account(mut)]
     pub student: Signer<'info>,
@@ -139,6 +698,7 @@ pub struct InitCheckResult<'info> {
         course.key().as_ref(),
         ASSIGNMENT_ID_SEED,
         assignment_id.as_ref(),
+        scope_seed(&scope),
     ],
     seeds::program = result_processor_program,
     bump)]
@@ -160,8 +720,10 @@ pub struct Check<'info> {
         course.key().as_ref(),
         ASSIGNMENT_ID_SEED,
         assignment_checker.assignment_id.as_ref(),
+        scope_seed(&assignment_checker.scope),
     ], seeds::program = result_processor_program, bump=assignment_checker.bump_seed,
-    constraint = assignment_checker.assignment_id == check_result.assignment_id
+    constraint = assignment_checker.assignment_id == check_result.assignment_id,
+    constraint = assignment_checker.scope == check_result.scope
     )]
     pub assignment_checker: Account<'info, AssignmentCheckerState>,
 
@@ -174,6 +736,7 @@ pub struct Check<'info> {
         course.key().as_ref(),
         ASSIGNMENT_ID_SEED,
         check_result.assignment_id.as_ref(),
+        scope_seed(&check_result.scope),
     ], seeds::program = result_processor_program, bump=check_result.bump_seed,
     )]
     pub check_result: Account<'info, CheckResult>,
@@ -182,6 +745,45 @@ pub struct Check<'info> {
     // student cannot call check directly
     #[account(executable)]
     pub result_processor_program: AccountInfo<'info>,
+    /// CHECK: instructions sysvar is read to verify grader attestations
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Commit<'info> {
+    pub student: Signer<'info>,
+    pub course: Account<'info, course_manager::Course>,
+
+    #[account(
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        assignment_checker.assignment_id.as_ref(),
+        scope_seed(&assignment_checker.scope),
+    ], seeds::program = result_processor_program, bump=assignment_checker.bump_seed,
+    constraint = assignment_checker.assignment_id == check_result.assignment_id,
+    constraint = assignment_checker.scope == check_result.scope
+    )]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+
+    // only the student can commit into own check result
+    #[account(mut,
+        signer,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        ASSIGNMENT_ID_SEED,
+        check_result.assignment_id.as_ref(),
+        scope_seed(&check_result.scope),
+    ], seeds::program = result_processor_program, bump=check_result.bump_seed,
+    )]
+    pub check_result: Account<'info, CheckResult>,
+    #[account(executable)]
+    pub result_processor_program: AccountInfo<'info>,
 }
 
 #[account]
@@ -200,16 +802,391 @@ pub struct AssignmentCheckerState {
     /// hash is applied `hash_chain_length` number of times
     ground_truth_hash_chain_tail: [u8; 32],
     pub bump_seed: u8,
+    /// Commit-reveal submission mode is on when it is set
+    ///
+    /// Minimal number of slots between `commit` and `reveal`
+    pub reveal_delay_slots: Option<u64>,
+    pub kind: CheckerKind,
+    /// Number of times the hash chain has been refilled
+    pub hash_chain_generation: u32,
+    /// Deadline of the assignment, the reward is flat when it is not set
+    pub reward_schedule: Option<RewardSchedule>,
+    /// The checker is course-wide when it is not set
+    pub scope: Option<Pubkey>,
+    /// Sub-answers of `CheckerKind::MultiPart` checker
+    pub parts: Vec<AssignmentPart>,
+    /// Answer key of `CheckerKind::Quiz` checker, empty until it is revealed
+    pub answer_key: Vec<u8>,
+    pub attempt_policy: AttemptPolicy,
+    /// Solutions are rejected while the checker is paused by the course authority
+    pub paused: bool,
+    /// Instructor who paid the rent, it is returned to them when the checker is closed
+    pub rent_payer: Pubkey,
 }
 
 impl AssignmentCheckerState {
-    pub const LEN: usize = 16 + 2 + 2 + 32 + 32 + 1;
+    pub const LEN: usize = 16
+        + 2
+        + 2
+        + 32
+        + 32
+        + 1
+        + (1 + 8)
+        + CheckerKind::LEN
+        + 4
+        + (1 + RewardSchedule::LEN)
+        + (1 + 32)
+        + (4 + AssignmentPart::LEN * MAX_PARTS)
+        + (4 + MAX_QUIZ_QUESTIONS)
+        + AttemptPolicy::LEN
+        + 1
+        + 32;
+
+    /// Number of tokens minted for the first successful check at the moment `now`
+    pub fn reward_at(&self, now: i64) -> u16 {
+        self.decayed_reward(self.to_mint_on_successful_check, now)
+    }
+
+    /// Number of tokens minted for the parts in the bitmap passed at the moment `now`
+    pub fn parts_reward_at(&self, parts: u16, now: i64) -> u16 {
+        let points = self
+            .parts
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| parts & (1 << index) != 0)
+            .fold(0u16, |points, (_, part)| points.saturating_add(part.points));
+        self.decayed_reward(points, now)
+    }
+
+    /// Number of tokens minted for the quiz score, proportional to the number of correct choices
+    ///
+    /// The reward of a quiz is not decayed, all choices are committed before the deadline
+    pub fn quiz_reward(&self, score: u16) -> u16 {
+        match self.kind {
+            CheckerKind::Quiz { question_count, .. } => {
+                (u32::from(self.to_mint_on_successful_check) * u32::from(score)
+                    / u32::from(question_count)) as u16
+            }
+            _ => 0,
+        }
+    }
+
+    /// Number of tokens minted for the attested score at the moment `now`
+    ///
+    /// The reward is proportional to the score out of the max score of the grader
+    pub fn attested_reward_at(&self, score: u16, now: i64) -> u16 {
+        match self.kind {
+            CheckerKind::GraderAttestation { max_score, .. } => {
+                let full_reward = u32::from(self.to_mint_on_successful_check)
+                    * u32::from(score.min(max_score))
+                    / u32::from(max_score);
+                self.decayed_reward(full_reward as u16, now)
+            }
+            _ => 0,
+        }
+    }
+
+    /// Bitmap of all parts of the checker
+    pub fn all_parts(&self) -> u16 {
+        ((1u32 << self.parts.len()) - 1) as u16
+    }
+
+    fn decayed_reward(&self, full_reward: u16, now: i64) -> u16 {
+        match &self.reward_schedule {
+            Some(schedule) => schedule.reward_at(full_reward, now),
+            None => full_reward,
+        }
+    }
+
+    fn require_active(&self) -> Result<()> {
+        if self.paused {
+            return Err(error!(AssignmentCheckerError::CheckerPaused));
+        }
+        Ok(())
+    }
+
+    fn require_accepting_solutions(&self) -> Result<()> {
+        self.require_active()?;
+        if let Some(cutoff_at) = self.reward_schedule.and_then(|schedule| schedule.cutoff_at) {
+            if Clock::get()?.unix_timestamp >= cutoff_at {
+                return Err(error!(AssignmentCheckerError::SubmissionCutoff));
+            }
+        }
+        Ok(())
+    }
 
     pub fn ground_truth_hash_chain_tail(&mut self) -> &mut [u8; 32] {
         &mut self.ground_truth_hash_chain_tail
     }
 }
 
+/// The way a checker verifies submitted solutions
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum CheckerKind {
+    /// Solution hashes are checked against the shared hash chain
+    ///
+    /// Each successful check cuts the tail of the chain
+    HashChain,
+    /// Each student has own answer hash `student_bound_answer_hash(salt, student, value)`
+    ///
+    /// The checker keeps the Merkle root of `student_bound_leaf(student, answer_hash)` leaves
+    /// and doesn't use the hash chain
+    StudentBound { answers_root: [u8; 32] },
+    /// A registered grader checks the solution off-chain and signs the score
+    ///
+    /// The student sends `grader_attestation_message(assignment_checker, student, score)`
+    /// signed by the grader through the ed25519 program instruction preceding the check.
+    /// The check passes when the score is at least `min_score`,
+    /// and the reward is proportional to the score out of `max_score`.
+    GraderAttestation {
+        grader: Pubkey,
+        min_score: u16,
+        max_score: u16,
+    },
+    /// Several sub-answers, each with own hash chain and points
+    ///
+    /// The parts are kept in `AssignmentCheckerState::parts` and share the checker salt.
+    /// The student submits any subset of parts and gets the points of the parts
+    /// passed the first time. The check passes when all parts have passed.
+    MultiPart,
+    /// Multiple-choice quiz with the answer key revealed after the deadline
+    ///
+    /// The checker keeps `answer_key_commitment(answer_key, nonce)` of the answer key with
+    /// a choice per question. Students commit `quiz_commitment_hash(student, choices, nonce)`
+    /// until `reward_schedule.cutoff_at`. The course authority reveals the answer key after it,
+    /// and students get their choices scored by `grade_quiz`.
+    Quiz {
+        answer_key_commitment: [u8; 32],
+        question_count: u8,
+    },
+}
+
+impl CheckerKind {
+    pub const LEN: usize = 1 + 32 + 2 + 2;
+}
+
+/// Settings of a new assignment checker
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct CheckerConfig {
+    pub hash_chain_length: u16,
+    pub to_mint_on_successful_check: u16,
+    pub salt: [u8; 32],
+    /// Creator of assignment checker is a trusted authority
+    /// It should precompute ground truth hash chain tail
+    /// to save nonfree compute operations of onchain program
+    /// and not to send the ground truth assignment result value to public blockchain
+    pub ground_truth_hash_chain_tail: [u8; 32],
+    /// Some(slots) enables commit-reveal submission mode
+    /// with the minimal number of slots between commit and reveal, HashChain only
+    pub reveal_delay_slots: Option<u64>,
+    /// HashChain uses hash_chain_length and ground_truth_hash_chain_tail,
+    /// other kinds ignore them
+    pub kind: CheckerKind,
+    /// None keeps the reward flat and accepts solutions forever
+    pub reward_schedule: Option<RewardSchedule>,
+    /// Sub-answers of MultiPart checker, empty for other kinds
+    pub parts: Vec<AssignmentPart>,
+    /// Limits of check attempts of each student
+    pub attempt_policy: AttemptPolicy,
+}
+
+impl CheckerConfig {
+    fn validate(&self) -> Result<()> {
+        if self.kind == CheckerKind::HashChain && self.hash_chain_length < 2 {
+            return Err(error!(AssignmentCheckerError::HashChainTooShort));
+        }
+        if self.kind != CheckerKind::HashChain && self.reveal_delay_slots.is_some() {
+            return Err(error!(AssignmentCheckerError::UnsupportedCheckerKind));
+        }
+        let parts_are_valid = match self.kind {
+            CheckerKind::MultiPart => {
+                !self.parts.is_empty()
+                    && self.parts.len() <= MAX_PARTS
+                    && self.parts.iter().all(|part| part.hash_chain_length >= 2)
+            }
+            _ => self.parts.is_empty(),
+        };
+        if !parts_are_valid {
+            return Err(error!(AssignmentCheckerError::InvalidParts));
+        }
+        if let CheckerKind::Quiz { question_count, .. } = self.kind {
+            let has_deadline = matches!(
+                self.reward_schedule,
+                Some(RewardSchedule {
+                    cutoff_at: Some(_),
+                    ..
+                })
+            );
+            if question_count == 0
+                || usize::from(question_count) > MAX_QUIZ_QUESTIONS
+                || !has_deadline
+            {
+                return Err(error!(AssignmentCheckerError::InvalidQuiz));
+            }
+        }
+        if let CheckerKind::GraderAttestation {
+            min_score,
+            max_score,
+            ..
+        } = self.kind
+        {
+            if max_score == 0 || min_score > max_score {
+                return Err(error!(AssignmentCheckerError::InvalidGraderScores));
+            }
+        }
+        if let Some(schedule) = self.reward_schedule {
+            if !schedule.is_valid() {
+                return Err(error!(AssignmentCheckerError::InvalidRewardSchedule));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Limits of check attempts of a student, they keep small answer spaces from being brute-forced
+///
+/// Each `check` and `reveal` call is an attempt, the default policy has no limits
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AttemptPolicy {
+    /// Maximal number of attempts, unlimited when it is not set
+    pub max_attempts: Option<u16>,
+    /// Minimal number of slots between two attempts
+    pub cooldown_slots: u64,
+}
+
+impl AttemptPolicy {
+    pub const LEN: usize = (1 + 2) + 8;
+}
+
+/// Sub-answer of `CheckerKind::MultiPart` checker
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct AssignmentPart {
+    /// Max number of successful checks of the part + 1
+    pub hash_chain_length: u16,
+    /// Hash chain tail of the part answer computed with the checker salt
+    pub ground_truth_hash_chain_tail: [u8; 32],
+    /// Number of tokens minted when the part passes the first time
+    pub points: u16,
+}
+
+impl AssignmentPart {
+    pub const LEN: usize = 2 + 32 + 2;
+}
+
+/// Solution of a single part of `CheckerKind::MultiPart` checker
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct PartSubmission {
+    /// Position of the part in the checker
+    pub index: u8,
+    pub expected_hash_chain_length: u16,
+    pub hash_chain_tail_parent: [u8; 32],
+}
+
+/// Assignment deadline and the reward policy for late solutions
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct RewardSchedule {
+    /// Unix timestamp until which the full reward is minted
+    pub due_at: i64,
+    pub decay: RewardDecay,
+    /// Unix timestamp since which solutions are rejected
+    pub cutoff_at: Option<i64>,
+}
+
+impl RewardSchedule {
+    pub const LEN: usize = 8 + RewardDecay::LEN + (1 + 8);
+
+    pub fn is_valid(&self) -> bool {
+        let decay_is_valid = match self.decay {
+            RewardDecay::None => true,
+            RewardDecay::Linear { decay_period, .. } => decay_period > 0,
+            RewardDecay::Stepwise { step_period, .. } => step_period > 0,
+        };
+        decay_is_valid && !matches!(self.cutoff_at, Some(cutoff_at) if cutoff_at < self.due_at)
+    }
+
+    /// Reward for the solution checked at the moment `now`
+    pub fn reward_at(&self, full_reward: u16, now: i64) -> u16 {
+        let late_for = now.saturating_sub(self.due_at);
+        if late_for <= 0 {
+            return full_reward;
+        }
+        let late_for = late_for as u64;
+        match self.decay {
+            RewardDecay::None => full_reward,
+            RewardDecay::Linear {
+                decay_period,
+                floor,
+            } => {
+                let floor = floor.min(full_reward);
+                let decay_period = decay_period as u64;
+                if late_for >= decay_period {
+                    return floor;
+                }
+                // full_reward - floor is reduced proportionally to the late time,
+                // the product doesn't fit into u64 for long decay periods
+                let decayed = u128::from(full_reward - floor) * u128::from(late_for)
+                    / u128::from(decay_period);
+                full_reward - decayed as u16
+            }
+            RewardDecay::Stepwise {
+                step_period,
+                step_amount,
+                floor,
+            } => {
+                let floor = floor.min(full_reward);
+                // each started step reduces the reward
+                let steps = (late_for - 1) / step_period as u64 + 1;
+                let decayed = steps.saturating_mul(step_amount.into());
+                u64::from(full_reward)
+                    .saturating_sub(decayed)
+                    .max(floor.into()) as u16
+            }
+        }
+    }
+}
+
+/// How the reward decreases after the due date
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RewardDecay {
+    /// Full reward until the cutoff
+    None,
+    /// The reward decreases linearly from the full one to `floor` during `decay_period` seconds
+    Linear { decay_period: i64, floor: u16 },
+    /// The reward decreases by `step_amount` each started `step_period` seconds down to `floor`
+    Stepwise {
+        step_period: i64,
+        step_amount: u16,
+        floor: u16,
+    },
+}
+
+impl RewardDecay {
+    pub const LEN: usize = 1 + 8 + 2 + 2;
+}
+
+/// Solution sent by a student, it should match the checker kind
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum Submission {
+    HashChain {
+        // used to validate the hash chain length
+        // that the client expects and deal with concurrent checks
+        expected_hash_chain_length: u16,
+        // the hash before current hash chain tail
+        hash_chain_tail_parent: [u8; 32],
+    },
+    StudentBound {
+        answer_hash: [u8; 32],
+        // sibling hashes from the leaf up to the root
+        proof: Vec<[u8; 32]>,
+    },
+    GraderAttestation {
+        score: u16,
+    },
+    MultiPart {
+        parts: Vec<PartSubmission>,
+    },
+}
+
 #[account]
 pub struct CheckResult {
     /// Assignment ID is unique within a course
@@ -218,10 +1195,46 @@ pub struct CheckResult {
     /// This is true only after first successful check
     pub passed_first_time: bool,
     pub bump_seed: u8,
+    /// Student's commitment waiting to be revealed
+    pub commitment: Option<[u8; 32]>,
+    pub committed_at_slot: u64,
+    /// The best score attested by the grader
+    pub score: u16,
+    /// Scope of the assignment checker
+    pub scope: Option<Pubkey>,
+    /// Bitmap of passed parts of `CheckerKind::MultiPart` checker
+    pub parts_passed: u16,
+    /// Number of check attempts of the student
+    pub attempts: u16,
+    pub last_attempt_slot: u64,
+    /// Unix timestamp of the first successful check
+    pub passed_at: Option<i64>,
 }
 
 impl CheckResult {
-    pub const LEN: usize = 16 + 1 + 1 + 1;
+    pub const LEN: usize = 16 + 1 + 1 + 1 + (1 + 32) + 8 + 2 + (1 + 32) + 2 + 2 + 8 + (1 + 8);
+
+    /// Count the attempt unless it exceeds the attempt policy of the checker
+    fn record_attempt(&mut self, policy: &AttemptPolicy) -> Result<()> {
+        if matches!(policy.max_attempts, Some(max_attempts) if self.attempts >= max_attempts) {
+            return Err(error!(AssignmentCheckerError::TooManyAttempts));
+        }
+        let slot = Clock::get()?.slot;
+        if self.attempts > 0 && slot < self.last_attempt_slot.saturating_add(policy.cooldown_slots)
+        {
+            return Err(error!(AssignmentCheckerError::AttemptCooldown));
+        }
+        self.attempts = self.attempts.saturating_add(1);
+        self.last_attempt_slot = slot;
+        Ok(())
+    }
+
+    fn record_first_pass(&mut self) -> Result<()> {
+        if self.passed_first_time && self.passed_at.is_none() {
+            self.passed_at = Some(Clock::get()?.unix_timestamp);
+        }
+        Ok(())
+    }
 }
 
 #[error_code]
@@ -230,4 +1243,466 @@ pub enum AssignmentCheckerError {
     ZeroHashChainLength,
     #[msg("The hash chain for this checker differs from provided expected hash chain length. Retry with updated expected length.")]
     ExpectedHashLengthDiffers,
+    #[msg("This checker accepts solutions only through commit and reveal")]
+    CommitRevealRequired,
+    #[msg("Commit-reveal submission mode is disabled for this checker")]
+    CommitRevealDisabled,
+    #[msg("There is no commitment to reveal")]
+    NoCommitment,
+    #[msg("Reveal delay has not passed since the commitment")]
+    RevealTooEarly,
+    #[msg("Revealed values do not match the commitment")]
+    CommitmentMismatch,
+    #[msg("Hash chain should have at least 2 hashes")]
+    HashChainTooShort,
+    #[msg("The submission does not match the kind of this checker")]
+    SubmissionKindMismatch,
+    #[msg("The operation is not supported by the kind of this checker")]
+    UnsupportedCheckerKind,
+    #[msg("Grader attestation is missing or invalid")]
+    InvalidAttestation,
+    #[msg("The signer is neither the course authority nor a co-instructor")]
+    NotCourseInstructor,
+    #[msg("The signer is not the course authority")]
+    NotCourseAuthority,
+    #[msg("Reward decay period should be positive and the cutoff should not precede the due date")]
+    InvalidRewardSchedule,
+    #[msg("The assignment no longer accepts solutions")]
+    SubmissionCutoff,
+    #[msg("Multi-part checker should have 1 to 16 parts with hash chains of at least 2 hashes, other kinds have no parts")]
+    InvalidParts,
+    #[msg("The checker has no part with this index")]
+    InvalidPartIndex,
+    #[msg("Quiz should have 1 to 64 questions and a cutoff of the reward schedule")]
+    InvalidQuiz,
+    #[msg("The quiz accepts choices until its deadline")]
+    QuizNotClosed,
+    #[msg("The answer key is already revealed")]
+    AnswerKeyAlreadyRevealed,
+    #[msg("The answer key is not revealed yet")]
+    AnswerKeyNotRevealed,
+    #[msg("The student has used all check attempts")]
+    TooManyAttempts,
+    #[msg("Cooldown has not passed since the previous check attempt")]
+    AttemptCooldown,
+    #[msg("The assignment checker is paused")]
+    CheckerPaused,
+    #[msg("Max score of the grader should be positive and not below the min score")]
+    InvalidGraderScores,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::sysvar::instructions::{
+        construct_instructions_data, store_current_index, BorrowedInstruction,
+    };
+
+    const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+    /// Ed25519 program instruction data with a single signature,
+    /// the instruction indexes of the signature, public key and message are given
+    fn ed25519_data(grader: &Pubkey, message: &[u8], instruction_indexes: [u16; 3]) -> Vec<u8> {
+        const PUBLIC_KEY_OFFSET: u16 = 16;
+        const SIGNATURE_OFFSET: u16 = PUBLIC_KEY_OFFSET + 32;
+        const MESSAGE_OFFSET: u16 = SIGNATURE_OFFSET + 64;
+        let [signature_index, public_key_index, message_index] = instruction_indexes;
+        let offsets = [
+            SIGNATURE_OFFSET,
+            signature_index,
+            PUBLIC_KEY_OFFSET,
+            public_key_index,
+            MESSAGE_OFFSET,
+            message.len() as u16,
+            message_index,
+        ];
+        let mut data = vec![1, 0];
+        for offset in offsets {
+            data.extend_from_slice(&offset.to_le_bytes());
+        }
+        data.extend_from_slice(grader.as_ref());
+        // the signature itself is verified by the ed25519 program
+        data.extend_from_slice(&[7; 64]);
+        data.extend_from_slice(message);
+        data
+    }
+
+    /// Whether the last of the (program id, data) instructions is signed in the previous one
+    fn is_signed(instructions: &[(Pubkey, Vec<u8>)], grader: &Pubkey, message: &[u8]) -> bool {
+        let borrowed: Vec<BorrowedInstruction> = instructions
+            .iter()
+            .map(|(program_id, data)| BorrowedInstruction {
+                program_id,
+                accounts: vec![],
+                data,
+            })
+            .collect();
+        let mut data = construct_instructions_data(&borrowed);
+        store_current_index(&mut data, instructions.len() as u16 - 1);
+        let key = sysvar::instructions::ID;
+        let owner = sysvar::ID;
+        let mut lamports = 0;
+        let account = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+        is_signed_in_previous_instruction(&account, grader, message).unwrap()
+    }
+
+    #[test]
+    fn valid_attestation_is_signed() {
+        let grader = Pubkey::new_unique();
+        let message = grader_attestation_message(&Pubkey::new_unique(), &Pubkey::new_unique(), 90);
+        let ed25519 = ed25519_data(&grader, &message, [CURRENT_INSTRUCTION; 3]);
+        assert!(is_signed(
+            &[(ed25519_program::ID, ed25519), (ID, vec![])],
+            &grader,
+            &message
+        ));
+    }
+
+    #[test]
+    fn attestation_of_another_grader_is_not_signed() {
+        let grader = Pubkey::new_unique();
+        let message = grader_attestation_message(&Pubkey::new_unique(), &Pubkey::new_unique(), 90);
+        let ed25519 = ed25519_data(&Pubkey::new_unique(), &message, [CURRENT_INSTRUCTION; 3]);
+        assert!(!is_signed(
+            &[(ed25519_program::ID, ed25519), (ID, vec![])],
+            &grader,
+            &message
+        ));
+    }
+
+    #[test]
+    fn another_message_is_not_signed() {
+        let grader = Pubkey::new_unique();
+        let checker = Pubkey::new_unique();
+        let student = Pubkey::new_unique();
+        let signed_message = grader_attestation_message(&checker, &student, 50);
+        let ed25519 = ed25519_data(&grader, &signed_message, [CURRENT_INSTRUCTION; 3]);
+        let instructions = [(ed25519_program::ID, ed25519), (ID, vec![])];
+        assert!(!is_signed(
+            &instructions,
+            &grader,
+            &grader_attestation_message(&checker, &student, 90)
+        ));
+        assert!(!is_signed(
+            &instructions,
+            &grader,
+            &grader_attestation_message(&checker, &Pubkey::new_unique(), 50)
+        ));
+    }
+
+    #[test]
+    fn data_of_another_instruction_is_not_signed() {
+        let grader = Pubkey::new_unique();
+        let message = grader_attestation_message(&Pubkey::new_unique(), &Pubkey::new_unique(), 90);
+        for instruction_indexes in [
+            [0, CURRENT_INSTRUCTION, CURRENT_INSTRUCTION],
+            [CURRENT_INSTRUCTION, 0, CURRENT_INSTRUCTION],
+            [CURRENT_INSTRUCTION, CURRENT_INSTRUCTION, 1],
+        ] {
+            let ed25519 = ed25519_data(&grader, &message, instruction_indexes);
+            assert!(!is_signed(
+                &[(ed25519_program::ID, ed25519), (ID, vec![])],
+                &grader,
+                &message
+            ));
+        }
+    }
+
+    #[test]
+    fn check_without_preceding_ed25519_instruction_is_not_signed() {
+        let grader = Pubkey::new_unique();
+        let message = grader_attestation_message(&Pubkey::new_unique(), &Pubkey::new_unique(), 90);
+        let ed25519 = ed25519_data(&grader, &message, [CURRENT_INSTRUCTION; 3]);
+        // the check is the first instruction
+        assert!(!is_signed(&[(ID, vec![])], &grader, &message));
+        // the ed25519 instruction is not right before the check
+        assert!(!is_signed(
+            &[
+                (ed25519_program::ID, ed25519.clone()),
+                (Pubkey::new_unique(), vec![]),
+                (ID, vec![])
+            ],
+            &grader,
+            &message
+        ));
+        // the same data in an instruction of another program
+        assert!(!is_signed(
+            &[(Pubkey::new_unique(), ed25519), (ID, vec![])],
+            &grader,
+            &message
+        ));
+    }
+
+    #[test]
+    fn malformed_ed25519_instruction_is_not_signed() {
+        let grader = Pubkey::new_unique();
+        let message = grader_attestation_message(&Pubkey::new_unique(), &Pubkey::new_unique(), 90);
+        let ed25519 = ed25519_data(&grader, &message, [CURRENT_INSTRUCTION; 3]);
+        // truncated offsets
+        assert!(!is_signed(
+            &[(ed25519_program::ID, ed25519[..10].to_vec()), (ID, vec![])],
+            &grader,
+            &message
+        ));
+        // the message is cut off
+        assert!(!is_signed(
+            &[
+                (ed25519_program::ID, ed25519[..ed25519.len() - 1].to_vec()),
+                (ID, vec![])
+            ],
+            &grader,
+            &message
+        ));
+        // two signatures
+        let mut two_signatures = ed25519;
+        two_signatures[0] = 2;
+        assert!(!is_signed(
+            &[(ed25519_program::ID, two_signatures), (ID, vec![])],
+            &grader,
+            &message
+        ));
+    }
+
+    const FULL_REWARD: u16 = 100;
+
+    fn schedule(decay: RewardDecay) -> RewardSchedule {
+        RewardSchedule {
+            due_at: 1_000,
+            decay,
+            cutoff_at: None,
+        }
+    }
+
+    fn linear() -> RewardSchedule {
+        schedule(RewardDecay::Linear {
+            decay_period: 100,
+            floor: 20,
+        })
+    }
+
+    fn stepwise() -> RewardSchedule {
+        schedule(RewardDecay::Stepwise {
+            step_period: 10,
+            step_amount: 15,
+            floor: 20,
+        })
+    }
+
+    #[test]
+    fn full_reward_until_deadline() {
+        for schedule in [schedule(RewardDecay::None), linear(), stepwise()] {
+            assert_eq!(schedule.reward_at(FULL_REWARD, 0), FULL_REWARD);
+            assert_eq!(schedule.reward_at(FULL_REWARD, 999), FULL_REWARD);
+            assert_eq!(schedule.reward_at(FULL_REWARD, 1_000), FULL_REWARD);
+        }
+        assert_eq!(
+            schedule(RewardDecay::None).reward_at(FULL_REWARD, 5_000),
+            FULL_REWARD
+        );
+    }
+
+    #[test]
+    fn linear_decay() {
+        let schedule = linear();
+        assert_eq!(schedule.reward_at(FULL_REWARD, 1_001), 100);
+        assert_eq!(schedule.reward_at(FULL_REWARD, 1_002), 99);
+        assert_eq!(schedule.reward_at(FULL_REWARD, 1_050), 60);
+        assert_eq!(schedule.reward_at(FULL_REWARD, 1_099), 21);
+        assert_eq!(schedule.reward_at(FULL_REWARD, 1_100), 20);
+        assert_eq!(schedule.reward_at(FULL_REWARD, 5_000), 20);
+    }
+
+    #[test]
+    fn stepwise_decay() {
+        let schedule = stepwise();
+        assert_eq!(schedule.reward_at(FULL_REWARD, 1_001), 85);
+        assert_eq!(schedule.reward_at(FULL_REWARD, 1_010), 85);
+        assert_eq!(schedule.reward_at(FULL_REWARD, 1_011), 70);
+        assert_eq!(schedule.reward_at(FULL_REWARD, 1_050), 25);
+        assert_eq!(schedule.reward_at(FULL_REWARD, 1_051), 20);
+        assert_eq!(schedule.reward_at(FULL_REWARD, 5_000), 20);
+    }
+
+    #[test]
+    fn floor_above_full_reward_is_capped() {
+        let schedule = schedule(RewardDecay::Linear {
+            decay_period: 100,
+            floor: 500,
+        });
+        assert_eq!(schedule.reward_at(FULL_REWARD, 1_050), FULL_REWARD);
+        assert_eq!(schedule.reward_at(FULL_REWARD, 5_000), FULL_REWARD);
+    }
+
+    #[test]
+    fn extreme_times_do_not_overflow() {
+        let long_linear = schedule(RewardDecay::Linear {
+            decay_period: i64::MAX,
+            floor: 0,
+        });
+        let long_stepwise = schedule(RewardDecay::Stepwise {
+            step_period: i64::MAX,
+            step_amount: u16::MAX,
+            floor: 0,
+        });
+        for schedule in [linear(), stepwise(), long_linear, long_stepwise] {
+            assert_eq!(schedule.reward_at(u16::MAX, i64::MIN), u16::MAX);
+        }
+        for schedule in [linear(), stepwise()] {
+            assert_eq!(schedule.reward_at(FULL_REWARD, i64::MAX), 20);
+        }
+        assert_eq!(long_linear.reward_at(u16::MAX, i64::MAX), 1);
+        assert_eq!(long_stepwise.reward_at(u16::MAX, i64::MAX), 0);
+        let due_at_min = RewardSchedule {
+            due_at: i64::MIN,
+            ..linear()
+        };
+        assert_eq!(due_at_min.reward_at(FULL_REWARD, i64::MAX), 20);
+    }
+
+    #[test]
+    fn schedule_validation() {
+        assert!(linear().is_valid());
+        assert!(RewardSchedule {
+            cutoff_at: Some(1_000),
+            ..stepwise()
+        }
+        .is_valid());
+        assert!(!RewardSchedule {
+            cutoff_at: Some(999),
+            ..stepwise()
+        }
+        .is_valid());
+        assert!(!schedule(RewardDecay::Linear {
+            decay_period: 0,
+            floor: 0
+        })
+        .is_valid());
+        assert!(!schedule(RewardDecay::Stepwise {
+            step_period: -1,
+            step_amount: 1,
+            floor: 0
+        })
+        .is_valid());
+    }
+
+    fn config(kind: CheckerKind, reveal_delay_slots: Option<u64>) -> CheckerConfig {
+        CheckerConfig {
+            hash_chain_length: 10,
+            to_mint_on_successful_check: 100,
+            salt: [0; 32],
+            ground_truth_hash_chain_tail: [0; 32],
+            reveal_delay_slots,
+            kind,
+            reward_schedule: None,
+            parts: vec![],
+            attempt_policy: AttemptPolicy::default(),
+        }
+    }
+
+    fn grader_attestation(min_score: u16, max_score: u16) -> CheckerKind {
+        CheckerKind::GraderAttestation {
+            grader: Pubkey::new_unique(),
+            min_score,
+            max_score,
+        }
+    }
+
+    fn error_code(result: Result<()>) -> Option<u32> {
+        match result {
+            Err(Error::AnchorError(error)) => Some(error.error_code_number),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn commit_reveal_is_hash_chain_only() {
+        assert!(config(CheckerKind::HashChain, Some(2)).validate().is_ok());
+        let student_bound = CheckerKind::StudentBound {
+            answers_root: [1; 32],
+        };
+        assert!(config(student_bound, None).validate().is_ok());
+
+        let unsupported = Some(u32::from(AssignmentCheckerError::UnsupportedCheckerKind));
+        let multi_part = CheckerConfig {
+            parts: vec![AssignmentPart {
+                hash_chain_length: 10,
+                ground_truth_hash_chain_tail: [0; 32],
+                points: 50,
+            }],
+            ..config(CheckerKind::MultiPart, Some(2))
+        };
+        assert_eq!(error_code(multi_part.validate()), unsupported);
+        for kind in [
+            student_bound,
+            CheckerKind::GraderAttestation {
+                grader: Pubkey::new_unique(),
+                min_score: 50,
+                max_score: 100,
+            },
+            CheckerKind::Quiz {
+                answer_key_commitment: [1; 32],
+                question_count: 4,
+            },
+        ] {
+            assert_eq!(error_code(config(kind, Some(2)).validate()), unsupported);
+        }
+    }
+
+    #[test]
+    fn grader_scores_validation() {
+        assert!(config(grader_attestation(50, 100), None).validate().is_ok());
+        assert!(config(grader_attestation(0, 1), None).validate().is_ok());
+        let invalid = Some(u32::from(AssignmentCheckerError::InvalidGraderScores));
+        assert_eq!(
+            error_code(config(grader_attestation(0, 0), None).validate()),
+            invalid
+        );
+        assert_eq!(
+            error_code(config(grader_attestation(101, 100), None).validate()),
+            invalid
+        );
+    }
+
+    #[test]
+    fn attested_reward_is_proportional_to_score() {
+        let mut checker = AssignmentCheckerState {
+            assignment_id: [0; 16],
+            hash_chain_length: 0,
+            to_mint_on_successful_check: 100,
+            salt: [0; 32],
+            ground_truth_hash_chain_tail: [0; 32],
+            bump_seed: 0,
+            reveal_delay_slots: None,
+            kind: grader_attestation(50, 80),
+            hash_chain_generation: 0,
+            reward_schedule: None,
+            scope: None,
+            parts: vec![],
+            answer_key: vec![],
+            attempt_policy: AttemptPolicy::default(),
+            paused: false,
+            rent_payer: Pubkey::default(),
+        };
+        assert_eq!(checker.attested_reward_at(80, 0), 100);
+        assert_eq!(checker.attested_reward_at(60, 0), 75);
+        assert_eq!(checker.attested_reward_at(1, 0), 1);
+        // scores above the max are rejected by the check, the reward is capped anyway
+        assert_eq!(checker.attested_reward_at(u16::MAX, 0), 100);
+
+        checker.reward_schedule = Some(linear());
+        assert_eq!(checker.attested_reward_at(60, 1_000), 75);
+        assert!(checker.attested_reward_at(60, 1_500) < 75);
+
+        checker.kind = CheckerKind::HashChain;
+        assert_eq!(checker.attested_reward_at(60, 0), 0);
+    }
 }
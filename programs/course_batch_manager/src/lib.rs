@@ -1,14 +1,26 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::pubkey::PUBKEY_BYTES;
-use anchor_spl::associated_token::AssociatedToken;
-use anchor_spl::token::{mint_to, Mint, MintTo, Token, TokenAccount};
+use anchor_lang::solana_program::{blake3, program::invoke_signed, pubkey::PUBKEY_BYTES, sysvar};
+use anchor_spl::associated_token::{get_associated_token_address, AssociatedToken};
+use anchor_spl::token::{
+    burn, freeze_account, mint_to, thaw_account, Burn, FreezeAccount, Mint, MintTo, ThawAccount,
+    Token, TokenAccount,
+};
 use assignment_checker::{
-    cpi::accounts::{Check, Init, InitCheckResult},
+    cpi::accounts::{Check, CloseChecker, Commit, Init, InitCheckResult, UpdateChecker},
     program::AssignmentChecker,
+    scope_seed,
+};
+pub use assignment_checker::{
+    AssignmentCheckerState, AssignmentPart, AttemptPolicy, CheckResult, CheckerConfig, CheckerKind,
+    PartSubmission, RewardDecay, RewardSchedule, Submission,
 };
-pub use assignment_checker::{AssignmentCheckerState, CheckResult};
 
-use course_manager::Course;
+use course_manager::{Course, CourseMetadata};
+use mpl_token_metadata::state::{DataV2, MAX_NAME_LENGTH, MAX_SYMBOL_LENGTH};
+use reputation::{
+    cpi::accounts::Record, program::Reputation as ReputationProgram, Config as ReputationConfig,
+    Reputation,
+};
 
 declare_id!("Po3YrSjzp5HM7VRFYszFM23LVJ58HHC9qoionaUgvRy");
 
@@ -18,18 +30,98 @@ pub const BATCH_MINT_SEED: &[u8; 10] = b"batch_mint";
 pub const BATCH_ID_SEED: &[u8; 15] = b"course_batch_id";
 pub const ASSIGNMENT_ID_SEED: &[u8; 13] = assignment_checker::ASSIGNMENT_ID_SEED;
 pub const STUDENT_ADDRESS_SEED: &[u8; 15] = assignment_checker::STUDENT_ADDRESS_SEED;
+pub const ENROLLMENT_SEED: &[u8; 10] = b"enrollment";
+pub const CERTIFICATE_POLICY_SEED: &[u8; 18] = b"certificate_policy";
+pub const CERTIFICATE_SEED: &[u8; 11] = b"certificate";
+pub const CERTIFICATE_MINT_SEED: &[u8; 16] = b"certificate_mint";
+pub const CATALOG_ITEM_SEED: &[u8; 12] = b"catalog_item";
+pub const REDEMPTION_SEED: &[u8; 10] = b"redemption";
+pub const MAX_REWARD_URI_LEN: usize = 200;
+/// Max number of assignments a certificate can require
+pub const MAX_REQUIRED_ASSIGNMENTS: usize = 16;
+pub const MAX_CERTIFICATE_URI_LEN: usize = 200;
+
+/// Merkle tree leaf of a student allowed to enroll the course batch
+pub fn allow_list_leaf(course_batch: &Pubkey, student: &Pubkey) -> [u8; 32] {
+    blake3::hashv(&[course_batch.as_ref(), student.as_ref()]).0
+}
+
+/// Token name shown by wallets, the course title followed by the batch id
+pub fn batch_token_name(course_title: &str, batch_id: &[u8; 16]) -> String {
+    truncate(
+        format!("{} {}", course_title, unpadded_id(batch_id)),
+        MAX_NAME_LENGTH,
+    )
+}
+
+/// Token symbol shown by wallets, the upper case course id
+pub fn batch_token_symbol(course_id: &[u8; 16]) -> String {
+    truncate(unpadded_id(course_id).to_uppercase(), MAX_SYMBOL_LENGTH)
+}
+
+/// Certificate NFT name, the course id and the batch id
+pub fn certificate_name(course_id: &[u8; 16], batch_id: &[u8; 16]) -> String {
+    truncate(
+        format!(
+            "{} {} certificate",
+            unpadded_id(course_id),
+            unpadded_id(batch_id)
+        ),
+        MAX_NAME_LENGTH,
+    )
+}
+
+/// Ids are padded with `_` up to 16 bytes
+fn unpadded_id(id: &[u8; 16]) -> String {
+    String::from_utf8_lossy(id)
+        .trim_end_matches('_')
+        .to_string()
+}
+
+fn truncate(mut text: String, max_len: usize) -> String {
+    if text.len() > max_len {
+        let end = (0..=max_len)
+            .rev()
+            .find(|end| text.is_char_boundary(*end))
+            .unwrap_or(0);
+        text.truncate(end);
+    }
+    text
+}
+
+/// Message signed by the inviter of `AllowList::Invitations` batch
+pub fn invitation_message(course_batch: &Pubkey, student: &Pubkey) -> Vec<u8> {
+    [course_batch.as_ref(), student.as_ref()].concat()
+}
 
 #[program]
 pub mod course_batch_manager {
     use super::*;
 
     /// Create data and mint accounts for course batch
-    pub fn create_new_batch(ctx: Context<NewCourseBatch>, batch_id: [u8; 16]) -> Result<()> {
+    ///
+    /// Tokens of a `soulbound` batch can't be transferred, the token accounts of the students stay frozen
+    pub fn create_new_batch(
+        ctx: Context<NewCourseBatch>,
+        batch_id: [u8; 16],
+        schedule: BatchSchedule,
+        enrollment_policy: EnrollmentPolicy,
+        soulbound: bool,
+    ) -> Result<()> {
+        if schedule.enrollment_opens_at > schedule.enrollment_closes_at
+            || schedule.starts_at > schedule.ends_at
+        {
+            return Err(error!(CourseBatchManagerError::InvalidSchedule));
+        }
         let course_batch_account = &mut ctx.accounts.course_batch;
         course_batch_account.id = batch_id;
         course_batch_account.course = ctx.accounts.course.key();
         course_batch_account.authority = ctx.accounts.authority.key();
         course_batch_account.mint = ctx.accounts.mint.key();
+        course_batch_account.syllabus_version = ctx.accounts.course_metadata.version;
+        course_batch_account.schedule = schedule;
+        course_batch_account.enrollment_policy = enrollment_policy;
+        course_batch_account.soulbound = soulbound;
         course_batch_account.bump_seed = *ctx
             .bumps
             .get("course_batch")
@@ -38,24 +130,467 @@ pub mod course_batch_manager {
         Ok(())
     }
 
-    /// Create Associated Token Account for given student wallet and mint
-    pub fn enroll_batch(_ctx: Context<EnrollBatch>) -> Result<()> {
-        // ATA is inited by Anchor
+    /// Create or update Metaplex metadata of the batch mint
+    ///
+    /// Name and symbol are derived from the course title, course id and batch id,
+    /// URI is the course content URI. The course batch PDA is the update authority.
+    pub fn set_batch_token_metadata(ctx: Context<SetBatchTokenMetadata>) -> Result<()> {
+        let set = ctx.accounts;
+        let data = DataV2 {
+            name: batch_token_name(&set.course_metadata.title, &set.course_batch.id),
+            symbol: batch_token_symbol(&set.course.id),
+            uri: set.course_metadata.content_uri.clone(),
+            seller_fee_basis_points: 0,
+            creators: None,
+            collection: None,
+            uses: None,
+        };
+
+        let course_key = set.course.key();
+        let course_batch_seeds = [
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            BATCH_ID_SEED,
+            set.course_batch.id.as_ref(),
+            BATCH_DATA_SEED,
+            &[set.course_batch.bump_seed],
+        ];
+        let signer_seeds = [course_batch_seeds.as_slice()];
+
+        let course_batch_key = set.course_batch.key();
+        if set.metadata.data_is_empty() {
+            let instruction = mpl_token_metadata::instruction::create_metadata_accounts_v2(
+                mpl_token_metadata::ID,
+                set.metadata.key(),
+                set.mint.key(),
+                course_batch_key,
+                set.authority.key(),
+                course_batch_key,
+                data.name,
+                data.symbol,
+                data.uri,
+                None,
+                0,
+                true,
+                true,
+                None,
+                None,
+            );
+            invoke_signed(
+                &instruction,
+                &[
+                    set.metadata.to_account_info(),
+                    set.mint.to_account_info(),
+                    set.course_batch.to_account_info(),
+                    set.authority.to_account_info(),
+                    set.system_program.to_account_info(),
+                    set.rent.to_account_info(),
+                    set.token_metadata_program.to_account_info(),
+                ],
+                signer_seeds.as_slice(),
+            )?;
+        } else {
+            let instruction = mpl_token_metadata::instruction::update_metadata_accounts_v2(
+                mpl_token_metadata::ID,
+                set.metadata.key(),
+                course_batch_key,
+                None,
+                Some(data),
+                None,
+                None,
+            );
+            invoke_signed(
+                &instruction,
+                &[
+                    set.metadata.to_account_info(),
+                    set.course_batch.to_account_info(),
+                    set.token_metadata_program.to_account_info(),
+                ],
+                signer_seeds.as_slice(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Configure what students of the course batch should achieve to claim the certificate
+    pub fn create_certificate_policy(
+        ctx: Context<CreateCertificatePolicy>,
+        requirement: CertificateRequirement,
+        // off-chain JSON of the certificate NFT
+        uri: String,
+    ) -> Result<()> {
+        if let CertificateRequirement::PassedAssignments { assignment_ids } = &requirement {
+            if assignment_ids.len() > MAX_REQUIRED_ASSIGNMENTS {
+                return Err(error!(CourseBatchManagerError::TooManyRequiredAssignments));
+            }
+        }
+        if uri.len() > MAX_CERTIFICATE_URI_LEN {
+            return Err(error!(CourseBatchManagerError::CertificateUriTooLong));
+        }
+        let policy_account = &mut ctx.accounts.certificate_policy;
+        policy_account.course_batch = ctx.accounts.course_batch.key();
+        policy_account.requirement = requirement;
+        policy_account.uri = uri;
+        policy_account.bump_seed = *ctx
+            .bumps
+            .get("certificate_policy")
+            .expect("certificate_policy pda is present");
+        Ok(())
+    }
+
+    /// Mint one-of-one certificate NFT to the student who has met the certificate requirement
+    ///
+    /// `PassedAssignments` requirement expects the `CheckResult` accounts of the required
+    /// assignments as remaining accounts in the same order.
+    /// The enrollment is marked as completed and the certificate can't be claimed twice.
+    pub fn claim_certificate<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimCertificate<'info>>,
+    ) -> Result<()> {
+        let claim = ctx.accounts;
+        let requirement_met = match &claim.certificate_policy.requirement {
+            CertificateRequirement::MinTokens { amount } => {
+                claim.enrollment.tokens_earned >= *amount
+            }
+            CertificateRequirement::PassedAssignments { assignment_ids } => {
+                assignment_ids.len() == ctx.remaining_accounts.len()
+                    && assignment_ids
+                        .iter()
+                        .zip(ctx.remaining_accounts)
+                        .map(|(assignment_id, account)| claim.has_passed(assignment_id, account))
+                        .collect::<Result<Vec<_>>>()?
+                        .into_iter()
+                        .all(|passed| passed)
+            }
+        };
+        if !requirement_met {
+            return Err(error!(
+                CourseBatchManagerError::CertificateRequirementNotMet
+            ));
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let certificate_account = &mut claim.certificate;
+        certificate_account.course_batch = claim.course_batch.key();
+        certificate_account.student = claim.student.key();
+        certificate_account.mint = claim.certificate_mint.key();
+        certificate_account.final_score = claim.enrollment.tokens_earned;
+        certificate_account.issued_at = now;
+        claim.enrollment.status = EnrollmentStatus::Completed;
+
+        let course_key = claim.course.key();
+        let course_batch_seeds = [
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            BATCH_ID_SEED,
+            claim.course_batch.id.as_ref(),
+            BATCH_DATA_SEED,
+            &[claim.course_batch.bump_seed],
+        ];
+        let signer_seeds = [course_batch_seeds.as_slice()];
+        mint_to(claim.mint_certificate_cpi_ctx(signer_seeds.as_slice()), 1)?;
+        claim.create_certificate_metadata(signer_seeds.as_slice())?;
+
+        let reputation_writer_seeds = [
+            reputation::WRITER_SEED.as_ref(),
+            &[*ctx
+                .bumps
+                .get("reputation_writer")
+                .expect("reputation_writer pda is present")],
+        ];
+        let signer_seeds = [reputation_writer_seeds.as_slice()];
+        if can_record_reputation(
+            &claim.reputation_config,
+            &claim.reputation,
+            &claim.reputation_writer.key(),
+            &claim.student.key(),
+        )? {
+            reputation::cpi::record_course_completed(
+                claim.record_cpi_ctx(signer_seeds.as_slice()),
+                course_key,
+            )?;
+        }
+        msg!(
+            "issued certificate {} to {}",
+            claim.certificate_mint.key(),
+            claim.student.key()
+        );
+        Ok(())
+    }
+
+    /// Publish a reward students of the course batch can redeem batch tokens for
+    pub fn create_catalog_item(
+        ctx: Context<CreateCatalogItem>,
+        item_id: [u8; 16],
+        cost: u64,
+        supply: u32,
+        // off-chain description of the reward
+        reward_uri: String,
+    ) -> Result<()> {
+        if reward_uri.len() > MAX_REWARD_URI_LEN {
+            return Err(error!(CourseBatchManagerError::RewardUriTooLong));
+        }
+        let item_account = &mut ctx.accounts.catalog_item;
+        item_account.course_batch = ctx.accounts.course_batch.key();
+        item_account.id = item_id;
+        item_account.cost = cost;
+        item_account.supply = supply;
+        item_account.reward_uri = reward_uri;
+        item_account.bump_seed = *ctx
+            .bumps
+            .get("catalog_item")
+            .expect("catalog_item pda is present");
+        Ok(())
+    }
+
+    /// Burn `catalog_item.cost` batch tokens of the student and issue the Redemption receipt
+    ///
+    /// Token account of a soulbound batch is thawed only for the time of burning
+    pub fn redeem(ctx: Context<Redeem>) -> Result<()> {
+        let redeem = ctx.accounts;
+        if redeem.catalog_item.redeemed >= redeem.catalog_item.supply {
+            return Err(error!(CourseBatchManagerError::CatalogItemSoldOut));
+        }
+
+        let course_batch_seeds = [
+            COURSE_DATA_SEED,
+            redeem.course_batch.course.as_ref(),
+            BATCH_ID_SEED,
+            redeem.course_batch.id.as_ref(),
+            BATCH_DATA_SEED,
+            &[redeem.course_batch.bump_seed],
+        ];
+        let signer_seeds = [course_batch_seeds.as_slice()];
+        if redeem.course_batch.soulbound {
+            thaw_account(redeem.thaw_cpi_ctx(signer_seeds.as_slice()))?;
+        }
+        burn(redeem.burn_cpi_ctx(), redeem.catalog_item.cost)?;
+        if redeem.course_batch.soulbound {
+            freeze_account(redeem.freeze_cpi_ctx(signer_seeds.as_slice()))?;
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let redemption_account = &mut redeem.redemption;
+        redemption_account.catalog_item = redeem.catalog_item.key();
+        redemption_account.student = redeem.student.key();
+        redemption_account.cost = redeem.catalog_item.cost;
+        redemption_account.redeemed_at = now;
+        redemption_account.bump_seed = *ctx
+            .bumps
+            .get("redemption")
+            .expect("redemption pda is present");
+        redeem.catalog_item.redeemed += 1;
+        msg!(
+            "redeemed {} for {} tokens of {}",
+            redeem.catalog_item.key(),
+            redeem.catalog_item.cost,
+            redeem.student.key()
+        );
+        Ok(())
+    }
+
+    /// Mark the redemption as fulfilled after the reward is handed over off-chain
+    ///
+    /// Only the course authority can fulfill it
+    pub fn fulfill_redemption(ctx: Context<FulfillRedemption>) -> Result<()> {
+        let redemption_account = &mut ctx.accounts.redemption;
+        if redemption_account.fulfilled_at.is_some() {
+            return Err(error!(CourseBatchManagerError::RedemptionAlreadyFulfilled));
+        }
+        redemption_account.fulfilled_at = Some(Clock::get()?.unix_timestamp);
+        Ok(())
+    }
+
+    /// Change the seat limit and the allow-list of the course batch
+    ///
+    /// Students who have already enrolled keep their seats
+    pub fn update_enrollment_policy(
+        ctx: Context<UpdateCourseBatch>,
+        enrollment_policy: EnrollmentPolicy,
+    ) -> Result<()> {
+        let course_batch_account = &mut ctx.accounts.course_batch;
+        if matches!(enrollment_policy.max_seats,
+            Some(max_seats) if max_seats < course_batch_account.enrolled_count)
+        {
+            return Err(error!(CourseBatchManagerError::SeatsBelowEnrollment));
+        }
+        course_batch_account.enrollment_policy = enrollment_policy;
+        Ok(())
+    }
+
+    /// Create Enrollment record and Associated Token Account for given student wallet and mint
+    ///
+    /// `allow_list_proof` is the Merkle proof of `allow_list_leaf` for `AllowList::MerkleRoot` batches,
+    /// `AllowList::Invitations` batches expect the ed25519 program instruction verifying
+    /// the inviter signature of `invitation_message` right before this one.
+    ///
+    /// Fails with `EnrollmentClosed` outside the enrollment window of the batch,
+    /// `BatchFull` when all seats are taken and `NotInvited` when the student is not on the allow-list
+    pub fn enroll_batch(ctx: Context<EnrollBatch>, allow_list_proof: Vec<[u8; 32]>) -> Result<()> {
+        // Enrollment and ATA are inited by Anchor
+        let now = Clock::get()?.unix_timestamp;
+        let course_batch_key = ctx.accounts.course_batch.key();
+        let student_key = ctx.accounts.student.key();
+        let course_batch_account = &mut ctx.accounts.course_batch;
+        if !course_batch_account.schedule.is_enrollment_open(now) {
+            return Err(error!(CourseBatchManagerError::EnrollmentClosed));
+        }
+        let policy = &course_batch_account.enrollment_policy;
+        if matches!(policy.max_seats,
+            Some(max_seats) if course_batch_account.enrolled_count >= max_seats)
+        {
+            return Err(error!(CourseBatchManagerError::BatchFull));
+        }
+        let invited = match policy.allow_list {
+            AllowList::Open => true,
+            AllowList::MerkleRoot { root } => assignment_checker::verify_merkle_proof(
+                &allow_list_proof,
+                &root,
+                allow_list_leaf(&course_batch_key, &student_key),
+            ),
+            AllowList::Invitations { inviter } => {
+                assignment_checker::is_signed_in_previous_instruction(
+                    &ctx.accounts.instructions,
+                    &inviter,
+                    &invitation_message(&course_batch_key, &student_key),
+                )?
+            }
+        };
+        if !invited {
+            return Err(error!(CourseBatchManagerError::NotInvited));
+        }
+        course_batch_account.enrolled_count += 1;
+
+        let enrollment_account = &mut ctx.accounts.enrollment;
+        enrollment_account.student = student_key;
+        enrollment_account.course_batch = course_batch_key;
+        enrollment_account.joined_at = now;
+        enrollment_account.status = EnrollmentStatus::Active;
+        enrollment_account.bump_seed = *ctx
+            .bumps
+            .get("enrollment")
+            .expect("enrollment pda is present");
+
+        let enroll = ctx.accounts;
+        if enroll.course_batch.soulbound {
+            let course_batch_seeds = [
+                COURSE_DATA_SEED,
+                enroll.course_batch.course.as_ref(),
+                BATCH_ID_SEED,
+                enroll.course_batch.id.as_ref(),
+                BATCH_DATA_SEED,
+                &[enroll.course_batch.bump_seed],
+            ];
+            let signer_seeds = [course_batch_seeds.as_slice()];
+            freeze_account(enroll.freeze_cpi_ctx(signer_seeds.as_slice()))?;
+        }
+        Ok(())
+    }
+
+    /// Leave the course batch
+    ///
+    /// The batch token account of the student is frozen and the seat is released
+    pub fn withdraw_from_batch(ctx: Context<WithdrawFromBatch>) -> Result<()> {
+        let withdraw = ctx.accounts;
+        if withdraw.enrollment.status != EnrollmentStatus::Active {
+            return Err(error!(CourseBatchManagerError::EnrollmentNotActive));
+        }
+
+        let course_key = withdraw.course_batch.course;
+        let course_batch_seeds = [
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            BATCH_ID_SEED,
+            withdraw.course_batch.id.as_ref(),
+            BATCH_DATA_SEED,
+            &[withdraw.course_batch.bump_seed],
+        ];
+        let signer_seeds = [course_batch_seeds.as_slice()];
+        // token accounts of soulbound batches are always frozen
+        if !withdraw.course_batch.soulbound {
+            freeze_account(withdraw.freeze_cpi_ctx(signer_seeds.as_slice()))?;
+        }
+
+        withdraw.enrollment.status = EnrollmentStatus::Withdrawn;
+        withdraw.course_batch.enrolled_count -= 1;
+        Ok(())
+    }
+
+    /// Remove the student from the course batch
+    ///
+    /// The batch token account of the student is frozen and the seat is released.
+    /// Only the course authority can expel the student
+    pub fn expel_student(ctx: Context<ManageEnrollment>) -> Result<()> {
+        let expel = ctx.accounts;
+        if expel.enrollment.status != EnrollmentStatus::Active {
+            return Err(error!(CourseBatchManagerError::EnrollmentNotActive));
+        }
+
+        let course_key = expel.course.key();
+        let course_batch_seeds = [
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            BATCH_ID_SEED,
+            expel.course_batch.id.as_ref(),
+            BATCH_DATA_SEED,
+            &[expel.course_batch.bump_seed],
+        ];
+        let signer_seeds = [course_batch_seeds.as_slice()];
+        // token accounts of soulbound batches are always frozen
+        if !expel.course_batch.soulbound {
+            freeze_account(expel.freeze_cpi_ctx(signer_seeds.as_slice()))?;
+        }
+
+        expel.enrollment.status = EnrollmentStatus::Expelled;
+        expel.course_batch.enrolled_count -= 1;
+        Ok(())
+    }
+
+    /// Return withdrawn or expelled student to the course batch
+    ///
+    /// The batch token account of the student is thawed, the seat limit is not checked.
+    /// Only the course authority can reinstate the student
+    pub fn reinstate_student(ctx: Context<ManageEnrollment>) -> Result<()> {
+        let reinstate = ctx.accounts;
+        if !matches!(
+            reinstate.enrollment.status,
+            EnrollmentStatus::Withdrawn | EnrollmentStatus::Expelled
+        ) {
+            return Err(error!(CourseBatchManagerError::NotRemovedFromBatch));
+        }
+
+        let course_key = reinstate.course.key();
+        let course_batch_seeds = [
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            BATCH_ID_SEED,
+            reinstate.course_batch.id.as_ref(),
+            BATCH_DATA_SEED,
+            &[reinstate.course_batch.bump_seed],
+        ];
+        let signer_seeds = [course_batch_seeds.as_slice()];
+        // token accounts of soulbound batches stay frozen
+        if !reinstate.course_batch.soulbound {
+            thaw_account(reinstate.thaw_cpi_ctx(signer_seeds.as_slice()))?;
+        }
+
+        reinstate.enrollment.status = EnrollmentStatus::Active;
+        reinstate.course_batch.enrolled_count += 1;
         Ok(())
     }
 
     /// Create an assignment checker
+    ///
+    /// A batch-scoped checker has own hash chain, reward and check results in each batch.
+    /// Only students of that batch can check it.
     pub fn create_assignment_checker(
         ctx: Context<CreateAssignmentChecker>,
         assignment_id: [u8; 16],
-        hash_chain_length: u16,
-        to_mint_on_successful_check: u16,
-        salt: [u8; 32],
+        // Some(course batch) derives the checker per batch, None shares it by all batches of the course
+        scope: Option<Pubkey>,
         // Creator of assignment checker is a trusted authority
-        // It should precompute ground truth hash chain tail
-        // to save nonfree compute operations of onchain program
+        // It should precompute ground truth hash chain tail of the config
         // and not to send the ground truth assignment result value to public blockchain
-        ground_truth_hash_chain_tail: [u8; 32],
+        config: CheckerConfig,
     ) -> Result<()> {
         // we don't own assignment_checker account
         let create = ctx.accounts;
@@ -66,6 +601,7 @@ pub mod course_batch_manager {
             course_key.as_ref(),
             ASSIGNMENT_ID_SEED,
             assignment_id.as_ref(),
+            scope_seed(&scope),
             &[*ctx
                 .bumps
                 .get("assignment_checker")
@@ -77,22 +613,184 @@ pub mod course_batch_manager {
         assignment_checker::cpi::init(
             create.init_cpi_ctx(signer_seeds.as_slice()),
             assignment_id,
+            scope,
+            config,
+        )?;
+        Ok(())
+    }
+
+    /// Replace the Merkle root of student answers of a student-bound assignment checker
+    ///
+    /// Used to add students who have enrolled after the checker is created.
+    /// Only the course authority can update it
+    pub fn update_answers_root(
+        ctx: Context<UpdateAssignmentChecker>,
+        answers_root: [u8; 32],
+    ) -> Result<()> {
+        let update = ctx.accounts;
+
+        let course_key = update.course.key();
+        let assignment_checker_seeds = [
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            update.assignment_checker.assignment_id.as_ref(),
+            scope_seed(&update.assignment_checker.scope),
+            &[update.assignment_checker.bump_seed],
+        ];
+        let signer_seeds = [assignment_checker_seeds.as_slice()];
+
+        assignment_checker::cpi::set_answers_root(
+            update.update_checker_cpi_ctx(signer_seeds.as_slice()),
+            answers_root,
+        )?;
+        Ok(())
+    }
+
+    /// Install a new hash chain into an assignment checker without recreating it
+    ///
+    /// Only the course authority can refill the chain
+    pub fn refill_hash_chain(
+        ctx: Context<UpdateAssignmentChecker>,
+        hash_chain_length: u16,
+        salt: [u8; 32],
+        ground_truth_hash_chain_tail: [u8; 32],
+    ) -> Result<()> {
+        let update = ctx.accounts;
+
+        let course_key = update.course.key();
+        let assignment_checker_seeds = [
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            update.assignment_checker.assignment_id.as_ref(),
+            scope_seed(&update.assignment_checker.scope),
+            &[update.assignment_checker.bump_seed],
+        ];
+        let signer_seeds = [assignment_checker_seeds.as_slice()];
+
+        assignment_checker::cpi::refill_hash_chain(
+            update.update_checker_cpi_ctx(signer_seeds.as_slice()),
             hash_chain_length,
-            to_mint_on_successful_check,
             salt,
             ground_truth_hash_chain_tail,
         )?;
         Ok(())
     }
 
+    /// Reveal the answer key of a quiz checker after the quiz deadline
+    ///
+    /// Only the course authority can reveal it
+    pub fn reveal_answer_key(
+        ctx: Context<UpdateAssignmentChecker>,
+        answer_key: Vec<u8>,
+        nonce: [u8; 32],
+    ) -> Result<()> {
+        let update = ctx.accounts;
+
+        let course_key = update.course.key();
+        let assignment_checker_seeds = [
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            update.assignment_checker.assignment_id.as_ref(),
+            scope_seed(&update.assignment_checker.scope),
+            &[update.assignment_checker.bump_seed],
+        ];
+        let signer_seeds = [assignment_checker_seeds.as_slice()];
+
+        assignment_checker::cpi::reveal_answer_key(
+            update.update_checker_cpi_ctx(signer_seeds.as_slice()),
+            answer_key,
+            nonce,
+        )?;
+        Ok(())
+    }
+
+    /// Pause the assignment checker, students can't submit solutions until it is resumed
+    ///
+    /// Used when the answer has leaked or the checker is broken.
+    /// Only the course authority can pause it
+    pub fn pause_assignment_checker(ctx: Context<UpdateAssignmentChecker>) -> Result<()> {
+        let update = ctx.accounts;
+
+        let course_key = update.course.key();
+        let assignment_checker_seeds = [
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            update.assignment_checker.assignment_id.as_ref(),
+            scope_seed(&update.assignment_checker.scope),
+            &[update.assignment_checker.bump_seed],
+        ];
+        let signer_seeds = [assignment_checker_seeds.as_slice()];
+
+        assignment_checker::cpi::set_paused(
+            update.update_checker_cpi_ctx(signer_seeds.as_slice()),
+            true,
+        )?;
+        Ok(())
+    }
+
+    /// Resume the paused assignment checker
+    ///
+    /// Only the course authority can resume it
+    pub fn resume_assignment_checker(ctx: Context<UpdateAssignmentChecker>) -> Result<()> {
+        let update = ctx.accounts;
+
+        let course_key = update.course.key();
+        let assignment_checker_seeds = [
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            update.assignment_checker.assignment_id.as_ref(),
+            scope_seed(&update.assignment_checker.scope),
+            &[update.assignment_checker.bump_seed],
+        ];
+        let signer_seeds = [assignment_checker_seeds.as_slice()];
+
+        assignment_checker::cpi::set_paused(
+            update.update_checker_cpi_ctx(signer_seeds.as_slice()),
+            false,
+        )?;
+        Ok(())
+    }
+
+    /// Close the assignment checker and return its rent to the instructor who created it
+    ///
+    /// Check results of students are kept.
+    /// Only the course authority can close it
+    pub fn close_assignment_checker(ctx: Context<CloseAssignmentChecker>) -> Result<()> {
+        let close = ctx.accounts;
+
+        let course_key = close.course.key();
+        let assignment_checker_seeds = [
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            close.assignment_checker.assignment_id.as_ref(),
+            scope_seed(&close.assignment_checker.scope),
+            &[close.assignment_checker.bump_seed],
+        ];
+        let signer_seeds = [assignment_checker_seeds.as_slice()];
+
+        assignment_checker::cpi::close(close.close_checker_cpi_ctx(signer_seeds.as_slice()))?;
+        Ok(())
+    }
+
     /// Start assignment solving
     ///
     /// CheckResult account is initialized
     ///
     /// Called by a student when he/she starts to solve the assignment
+    ///
+    /// Fails with `EnrollmentNotActive` unless the student is actively enrolled into the batch.
+    /// The scope should be the scope of the assignment checker,
+    /// a batch-scoped check result can be created only in that batch.
     pub fn create_check_result(
         ctx: Context<CreateCheckResult>,
         assignment_id: [u8; 16],
+        scope: Option<Pubkey>,
     ) -> Result<()> {
         let create = ctx.accounts;
 
@@ -105,6 +803,7 @@ pub mod course_batch_manager {
             course_key.as_ref(),
             ASSIGNMENT_ID_SEED,
             assignment_id.as_ref(),
+            scope_seed(&scope),
             &[*ctx
                 .bumps
                 .get("check_result")
@@ -116,17 +815,22 @@ pub mod course_batch_manager {
         assignment_checker::cpi::init_check_result(
             create.init_check_result_cpi_ctx(signer_seeds.as_slice()),
             assignment_id,
+            scope,
         )?;
         Ok(())
     }
 
     /// Check assignment solution and mint `assignment_checker.to_mint_on_successful_check` tokens when the check is succeded
-    pub fn check_assignment(
-        ctx: Context<CheckAssignment>,
-        expected_hash_chain_length: u16,
-        hash_chain_tail_parent: [u8; 32],
-    ) -> Result<()> {
+    ///
+    /// Multi-part checkers mint the points of the parts passed the first time instead.
+    /// Late solutions get the reward decayed according to `assignment_checker.reward_schedule`
+    ///
+    /// Fails with `BatchNotActive` outside the batch period,
+    /// with `EnrollmentNotActive` unless the student is actively enrolled into the batch
+    /// and with `CheckerScopeMismatch` when the checker is limited to another batch
+    pub fn check_assignment(ctx: Context<CheckAssignment>, submission: Submission) -> Result<()> {
         let check = ctx.accounts;
+        check.require_active_batch()?;
 
         let course_key = check.course.key();
         let assignment_checker_seeds = [
@@ -134,6 +838,7 @@ pub mod course_batch_manager {
             course_key.as_ref(),
             ASSIGNMENT_ID_SEED,
             check.assignment_checker.assignment_id.as_ref(),
+            scope_seed(&check.assignment_checker.scope),
             &[check.assignment_checker.bump_seed],
         ];
 
@@ -146,6 +851,7 @@ pub mod course_batch_manager {
             course_key.as_ref(),
             assignment_checker::ASSIGNMENT_ID_SEED,
             check.check_result.assignment_id.as_ref(),
+            scope_seed(&check.check_result.scope),
             &[check.check_result.bump_seed],
         ];
         let signer_seeds = [
@@ -153,45 +859,151 @@ pub mod course_batch_manager {
             check_result_seeds.as_slice(),
         ];
 
-        assignment_checker::cpi::check(
-            check.check_cpi_ctx(signer_seeds.as_slice()),
-            expected_hash_chain_length,
-            hash_chain_tail_parent,
-        )?;
+        let parts_passed_before = check.check_result.parts_passed;
+        assignment_checker::cpi::check(check.check_cpi_ctx(signer_seeds.as_slice()), submission)?;
 
-        // deserialize check_result again after assignment checker has changed the account
-        check.check_result.reload()?;
+        check.mint_on_first_pass(
+            parts_passed_before,
+            *ctx.bumps
+                .get("reputation_writer")
+                .expect("reputation_writer pda is present"),
+        )
+    }
 
-        let check_result = &check.check_result;
-        msg!(
-            "check_passed: {}, passed_first_time: {}",
-            check_result.check_passed,
-            check_result.passed_first_time
-        );
-        if check_result.check_passed && check_result.passed_first_time {
-            let mint_seeds = [
-                COURSE_DATA_SEED,
-                course_key.as_ref(),
-                BATCH_ID_SEED,
-                check.course_batch.id.as_ref(),
-                BATCH_MINT_SEED,
-                &[check.course_batch.mint_bump_seed],
-            ];
-            let course_batch_seeds = [
-                COURSE_DATA_SEED,
-                course_key.as_ref(),
-                BATCH_ID_SEED,
-                check.course_batch.id.as_ref(),
-                BATCH_DATA_SEED,
-                &[check.course_batch.bump_seed],
-            ];
-            let signer_seeds = [mint_seeds.as_slice(), course_batch_seeds.as_slice()];
-            let amount = check.assignment_checker.to_mint_on_successful_check.into();
-            mint_to(check.mint_to_cpi_ctx(signer_seeds.as_slice()), amount)?;
-            msg!("minted {} tokens to {}", amount, check.student.key());
-        }
+    /// Commit to a solution hash of the assignment checker in commit-reveal mode or to quiz choices
+    ///
+    /// `commitment` is `assignment_checker::commitment_hash(student, hash_chain_tail_parent, nonce)`
+    /// or `assignment_checker::quiz_commitment_hash(student, choices, nonce)` for quiz checkers
+    pub fn commit_assignment(ctx: Context<CommitAssignment>, commitment: [u8; 32]) -> Result<()> {
+        let commit = ctx.accounts;
+
+        let student_key = commit.student.key();
+        let course_key = commit.course.key();
+        let check_result_seeds = [
+            STUDENT_ADDRESS_SEED,
+            student_key.as_ref(),
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            commit.check_result.assignment_id.as_ref(),
+            scope_seed(&commit.check_result.scope),
+            &[commit.check_result.bump_seed],
+        ];
+        let signer_seeds = [check_result_seeds.as_slice()];
+
+        assignment_checker::cpi::commit(
+            commit.commit_cpi_ctx(signer_seeds.as_slice()),
+            commitment,
+        )?;
         Ok(())
     }
+
+    /// Reveal committed solution hash and mint `assignment_checker.to_mint_on_successful_check` tokens when the check is succeded
+    pub fn reveal_assignment(
+        ctx: Context<CheckAssignment>,
+        expected_hash_chain_length: u16,
+        hash_chain_tail_parent: [u8; 32],
+        nonce: [u8; 32],
+    ) -> Result<()> {
+        let check = ctx.accounts;
+        check.require_active_batch()?;
+
+        let course_key = check.course.key();
+        let assignment_checker_seeds = [
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            check.assignment_checker.assignment_id.as_ref(),
+            scope_seed(&check.assignment_checker.scope),
+            &[check.assignment_checker.bump_seed],
+        ];
+
+        let student_key = check.student.key();
+
+        let check_result_seeds = [
+            STUDENT_ADDRESS_SEED,
+            student_key.as_ref(),
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            check.check_result.assignment_id.as_ref(),
+            scope_seed(&check.check_result.scope),
+            &[check.check_result.bump_seed],
+        ];
+        let signer_seeds = [
+            assignment_checker_seeds.as_slice(),
+            check_result_seeds.as_slice(),
+        ];
+
+        let parts_passed_before = check.check_result.parts_passed;
+        assignment_checker::cpi::reveal(
+            check.check_cpi_ctx(signer_seeds.as_slice()),
+            expected_hash_chain_length,
+            hash_chain_tail_parent,
+            nonce,
+        )?;
+
+        check.mint_on_first_pass(
+            parts_passed_before,
+            *ctx.bumps
+                .get("reputation_writer")
+                .expect("reputation_writer pda is present"),
+        )
+    }
+
+    /// Grade committed quiz choices and mint the reward proportional to the score
+    ///
+    /// The answer key should be revealed by `reveal_answer_key` first.
+    /// `to_mint_on_successful_check` tokens are minted for all correct choices.
+    pub fn grade_quiz(
+        ctx: Context<CheckAssignment>,
+        choices: Vec<u8>,
+        nonce: [u8; 32],
+    ) -> Result<()> {
+        let check = ctx.accounts;
+        check.require_active_batch()?;
+
+        let course_key = check.course.key();
+        let assignment_checker_seeds = [
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            check.assignment_checker.assignment_id.as_ref(),
+            scope_seed(&check.assignment_checker.scope),
+            &[check.assignment_checker.bump_seed],
+        ];
+
+        let student_key = check.student.key();
+
+        let check_result_seeds = [
+            STUDENT_ADDRESS_SEED,
+            student_key.as_ref(),
+            COURSE_DATA_SEED,
+            course_key.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            check.check_result.assignment_id.as_ref(),
+            scope_seed(&check.check_result.scope),
+            &[check.check_result.bump_seed],
+        ];
+        let signer_seeds = [
+            assignment_checker_seeds.as_slice(),
+            check_result_seeds.as_slice(),
+        ];
+
+        let parts_passed_before = check.check_result.parts_passed;
+        assignment_checker::cpi::grade_quiz(
+            check.check_cpi_ctx(signer_seeds.as_slice()),
+            choices,
+            nonce,
+        )?;
+
+        check.mint_on_first_pass(
+            parts_passed_before,
+            *ctx.bumps
+                .get("reputation_writer")
+                .expect("reputation_writer pda is present"),
+        )
+    }
 }
 
 pub fn batch_canonical_pda(course_address: Pubkey, batch_id: &[u8; 16]) -> Pubkey {
@@ -238,6 +1050,81 @@ pub fn assignment_checker_canonical_pda(
     .0
 }
 
+/// Address of the assignment checker limited to the course batch
+pub fn batch_assignment_checker_canonical_pda(
+    course_address: Pubkey,
+    course_batch: Pubkey,
+    assignment_id: &[u8; 16],
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            COURSE_DATA_SEED,
+            course_address.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            assignment_id,
+            course_batch.as_ref(),
+        ],
+        &ID,
+    )
+    .0
+}
+
+pub fn enrollment_canonical_pda(student_address: Pubkey, course_batch: Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            STUDENT_ADDRESS_SEED,
+            student_address.as_ref(),
+            BATCH_DATA_SEED,
+            course_batch.as_ref(),
+            ENROLLMENT_SEED,
+        ],
+        &ID,
+    )
+    .0
+}
+
+pub fn catalog_item_canonical_pda(course_batch: Pubkey, item_id: &[u8; 16]) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            BATCH_DATA_SEED,
+            course_batch.as_ref(),
+            CATALOG_ITEM_SEED,
+            item_id,
+        ],
+        &ID,
+    )
+    .0
+}
+
+/// Address of the redemption receipt number `index` of the catalog item
+pub fn redemption_canonical_pda(catalog_item: Pubkey, index: u32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[catalog_item.as_ref(), REDEMPTION_SEED, &index.to_le_bytes()],
+        &ID,
+    )
+    .0
+}
+
+pub fn certificate_policy_canonical_pda(course_batch: Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            BATCH_DATA_SEED,
+            course_batch.as_ref(),
+            CERTIFICATE_POLICY_SEED,
+        ],
+        &ID,
+    )
+    .0
+}
+
+pub fn certificate_canonical_pda(enrollment: Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[enrollment.as_ref(), CERTIFICATE_SEED], &ID).0
+}
+
+pub fn certificate_mint_canonical_pda(enrollment: Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[enrollment.as_ref(), CERTIFICATE_MINT_SEED], &ID).0
+}
+
 pub fn check_result_canonical_pda(
     student_address: Pubkey,
     course_data: Pubkey,
@@ -257,13 +1144,64 @@ pub fn check_result_canonical_pda(
     .0
 }
 
+/// Address of the check result of the assignment checker limited to the course batch
+pub fn batch_check_result_canonical_pda(
+    student_address: Pubkey,
+    course_data: Pubkey,
+    course_batch: Pubkey,
+    assignment_id: &[u8; 16],
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            STUDENT_ADDRESS_SEED,
+            student_address.as_ref(),
+            COURSE_DATA_SEED,
+            course_data.as_ref(),
+            ASSIGNMENT_ID_SEED,
+            assignment_id,
+            course_batch.as_ref(),
+        ],
+        &ID,
+    )
+    .0
+}
+
+/// Whether the reputation of the student can be updated by the program
+///
+/// Minting doesn't depend on the reputation program, the update is skipped
+/// until the registry is created and lists the program as a writer,
+/// and while the student hasn't created the reputation account.
+/// The reputation of another student is rejected.
+fn can_record_reputation(
+    config: &AccountInfo,
+    reputation: &AccountInfo,
+    writer: &Pubkey,
+    student: &Pubkey,
+) -> Result<bool> {
+    if config.owner != &reputation::ID || reputation.owner != &reputation::ID {
+        return Ok(false);
+    }
+    let config: Account<ReputationConfig> = Account::try_from(config)?;
+    if !config.writers.contains(writer) {
+        msg!("the program is not a reputation writer, the reputation is not updated");
+        return Ok(false);
+    }
+    let reputation: Account<Reputation> = Account::try_from(reputation)?;
+    if reputation.student != *student {
+        return Err(error!(CourseBatchManagerError::ReputationStudentMismatch));
+    }
+    Ok(true)
+}
+
 #[derive(Accounts)]
 #[instruction(batch_id: [u8; 16])]
 pub struct NewCourseBatch<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
-    #[account(has_one = authority)]
+    #[account(constraint = course.is_instructor(&authority.key()) @ CourseBatchManagerError::NotCourseInstructor)]
     pub course: Account<'info, Course>,
+    #[account(has_one = course)]
+    pub course_metadata: Account<'info, CourseMetadata>,
     #[account(init, payer = authority, space = 8 + CourseBatch::LEN, seeds= [
         COURSE_DATA_SEED,
         course.key().as_ref(),
@@ -290,46 +1228,538 @@ pub struct NewCourseBatch<'info> {
 }
 
 #[derive(Accounts)]
-pub struct EnrollBatch<'info> {
-    #[account(mut)]
-    pub student: Signer<'info>,
-    // course authority
-    pub authority: AccountInfo<'info>,
-    #[account(has_one = authority, has_one = mint)]
+pub struct SetBatchTokenMetadata<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(constraint = course.is_instructor(&authority.key()) @ CourseBatchManagerError::NotCourseInstructor)]
+    pub course: Account<'info, Course>,
+    #[account(has_one = course)]
+    pub course_metadata: Account<'info, CourseMetadata>,
+    #[account(has_one = course, has_one = mint)]
+    pub course_batch: Account<'info, CourseBatch>,
+    pub mint: Account<'info, Mint>,
+    /// CHECK: metadata account is created and validated by the token metadata program
+    #[account(mut, seeds=[
+        mpl_token_metadata::state::PREFIX.as_bytes(),
+        mpl_token_metadata::ID.as_ref(),
+        mint.key().as_ref(),
+    ], bump, seeds::program = token_metadata_program.key())]
+    pub metadata: UncheckedAccount<'info>,
+    /// CHECK: the course batch PDA signs for the token metadata program only
+    #[account(address = mpl_token_metadata::ID)]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CreateCertificatePolicy<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(constraint = course.is_instructor(&authority.key()) @ CourseBatchManagerError::NotCourseInstructor)]
+    pub course: Account<'info, Course>,
+    #[account(has_one = course)]
+    pub course_batch: Account<'info, CourseBatch>,
+    #[account(init, payer = authority, space = 8 + CertificatePolicy::LEN, seeds=[
+        BATCH_DATA_SEED,
+        course_batch.key().as_ref(),
+        CERTIFICATE_POLICY_SEED,
+    ], bump)]
+    pub certificate_policy: Account<'info, CertificatePolicy>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimCertificate<'info> {
+    #[account(mut)]
+    pub student: Signer<'info>,
+    pub course: Box<Account<'info, Course>>,
+    #[account(has_one = course,
+        seeds=[
+        COURSE_DATA_SEED,
+        course.key().as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        BATCH_DATA_SEED,
+    ], bump=course_batch.bump_seed)]
+    pub course_batch: Box<Account<'info, CourseBatch>>,
+    #[account(has_one = course_batch, seeds=[
+        BATCH_DATA_SEED,
+        course_batch.key().as_ref(),
+        CERTIFICATE_POLICY_SEED,
+    ], bump=certificate_policy.bump_seed)]
+    pub certificate_policy: Box<Account<'info, CertificatePolicy>>,
+    #[account(mut, has_one = student, has_one = course_batch,
+        constraint = enrollment.status == EnrollmentStatus::Active @ CourseBatchManagerError::EnrollmentNotActive,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        BATCH_DATA_SEED,
+        course_batch.key().as_ref(),
+        ENROLLMENT_SEED,
+    ], bump=enrollment.bump_seed)]
+    pub enrollment: Box<Account<'info, Enrollment>>,
+    // the certificate record is derived from the enrollment, so it can be claimed only once
+    #[account(init, payer = student, space = 8 + Certificate::LEN, seeds=[
+        enrollment.key().as_ref(),
+        CERTIFICATE_SEED,
+    ], bump)]
+    pub certificate: Box<Account<'info, Certificate>>,
+    #[account(init, payer = student,
+        mint::authority = course_batch,
+        mint::decimals = 0,
+        mint::freeze_authority = course_batch,
+        seeds=[
+        enrollment.key().as_ref(),
+        CERTIFICATE_MINT_SEED,
+    ], bump)]
+    pub certificate_mint: Box<Account<'info, Mint>>,
+    #[account(
+        init,
+        payer = student,
+        associated_token::mint = certificate_mint,
+        associated_token::authority = student,
+    )]
+    pub certificate_token: Box<Account<'info, TokenAccount>>,
+    /// CHECK: metadata account is created and validated by the token metadata program
+    #[account(mut, seeds=[
+        mpl_token_metadata::state::PREFIX.as_bytes(),
+        mpl_token_metadata::ID.as_ref(),
+        certificate_mint.key().as_ref(),
+    ], bump, seeds::program = token_metadata_program.key())]
+    pub certificate_metadata: UncheckedAccount<'info>,
+    /// CHECK: master edition account is created and validated by the token metadata program
+    #[account(mut, seeds=[
+        mpl_token_metadata::state::PREFIX.as_bytes(),
+        mpl_token_metadata::ID.as_ref(),
+        certificate_mint.key().as_ref(),
+        mpl_token_metadata::state::EDITION.as_bytes(),
+    ], bump, seeds::program = token_metadata_program.key())]
+    pub certificate_master_edition: UncheckedAccount<'info>,
+    /// CHECK: the course batch PDA signs for the token metadata program only
+    #[account(address = mpl_token_metadata::ID)]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>,
+    /// CHECK: the registry is optional, the reputation is updated only when it exists
+    #[account(seeds = [reputation::CONFIG_SEED], bump, seeds::program = reputation_program.key())]
+    pub reputation_config: UncheckedAccount<'info>,
+    /// CHECK: reputation of the student is optional, it is updated only when the account exists
+    #[account(mut)]
+    pub reputation: UncheckedAccount<'info>,
+    /// CHECK: signer PDA of the program recognised by the reputation program
+    #[account(seeds = [reputation::WRITER_SEED], bump)]
+    pub reputation_writer: UncheckedAccount<'info>,
+    pub reputation_program: Program<'info, ReputationProgram>,
+}
+
+impl<'info> ClaimCertificate<'info> {
+    /// Whether the account is the passed check result of the student for the assignment
+    fn has_passed(&self, assignment_id: &[u8; 16], account: &AccountInfo<'info>) -> Result<bool> {
+        // the owner is checked by the deserialization
+        let check_result: Account<CheckResult> = Account::try_from(account)?;
+        // results of checkers limited to other batches don't count
+        if matches!(check_result.scope, Some(scope) if scope != self.course_batch.key()) {
+            return Ok(false);
+        }
+        let student_key = self.student.key();
+        let course_key = self.course.key();
+        let check_result_address = Pubkey::create_program_address(
+            &[
+                STUDENT_ADDRESS_SEED,
+                student_key.as_ref(),
+                COURSE_DATA_SEED,
+                course_key.as_ref(),
+                ASSIGNMENT_ID_SEED,
+                assignment_id.as_ref(),
+                scope_seed(&check_result.scope),
+                &[check_result.bump_seed],
+            ],
+            &ID,
+        )
+        .map_err(|_| error!(CourseBatchManagerError::CertificateRequirementNotMet))?;
+        Ok(check_result_address == account.key() && check_result.check_passed)
+    }
+
+    /// Create the metadata and the master edition with zero max supply of the certificate mint
+    ///
+    /// The master edition takes over the mint authority, so no more certificate tokens can be minted
+    fn create_certificate_metadata(&self, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+        let course_batch_key = self.course_batch.key();
+        let create_metadata = mpl_token_metadata::instruction::create_metadata_accounts_v2(
+            mpl_token_metadata::ID,
+            self.certificate_metadata.key(),
+            self.certificate_mint.key(),
+            course_batch_key,
+            self.student.key(),
+            course_batch_key,
+            certificate_name(&self.course.id, &self.course_batch.id),
+            batch_token_symbol(&self.course.id),
+            self.certificate_policy.uri.clone(),
+            None,
+            0,
+            true,
+            false,
+            None,
+            None,
+        );
+        invoke_signed(
+            &create_metadata,
+            &[
+                self.certificate_metadata.to_account_info(),
+                self.certificate_mint.to_account_info(),
+                self.course_batch.to_account_info(),
+                self.student.to_account_info(),
+                self.system_program.to_account_info(),
+                self.rent.to_account_info(),
+                self.token_metadata_program.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
+
+        let create_master_edition = mpl_token_metadata::instruction::create_master_edition_v3(
+            mpl_token_metadata::ID,
+            self.certificate_master_edition.key(),
+            self.certificate_mint.key(),
+            course_batch_key,
+            course_batch_key,
+            self.certificate_metadata.key(),
+            self.student.key(),
+            Some(0),
+        );
+        invoke_signed(
+            &create_master_edition,
+            &[
+                self.certificate_master_edition.to_account_info(),
+                self.certificate_mint.to_account_info(),
+                self.course_batch.to_account_info(),
+                self.student.to_account_info(),
+                self.certificate_metadata.to_account_info(),
+                self.token_program.to_account_info(),
+                self.system_program.to_account_info(),
+                self.rent.to_account_info(),
+                self.token_metadata_program.to_account_info(),
+            ],
+            signer_seeds,
+        )?;
+        Ok(())
+    }
+}
+
+impl<'a, 'b, 'c, 'info> ClaimCertificate<'info> {
+    pub fn record_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, Record<'info>> {
+        let cpi_program = self.reputation_program.to_account_info();
+
+        let cpi_accounts = Record {
+            writer: self.reputation_writer.to_account_info(),
+            config: self.reputation_config.to_account_info(),
+            reputation: self.reputation.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn mint_certificate_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, MintTo<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = MintTo {
+            mint: self.certificate_mint.to_account_info(),
+            to: self.certificate_token.to_account_info(),
+            authority: self.course_batch.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(item_id: [u8; 16])]
+pub struct CreateCatalogItem<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(constraint = course.is_instructor(&authority.key()) @ CourseBatchManagerError::NotCourseInstructor)]
+    pub course: Account<'info, Course>,
+    #[account(has_one = course)]
+    pub course_batch: Account<'info, CourseBatch>,
+    #[account(init, payer = authority, space = 8 + CatalogItem::LEN, seeds=[
+        BATCH_DATA_SEED,
+        course_batch.key().as_ref(),
+        CATALOG_ITEM_SEED,
+        item_id.as_ref(),
+    ], bump)]
+    pub catalog_item: Account<'info, CatalogItem>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Redeem<'info> {
+    #[account(mut)]
+    pub student: Signer<'info>,
+    #[account(has_one = mint,
+        seeds=[
+        COURSE_DATA_SEED,
+        course_batch.course.as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        BATCH_DATA_SEED,
+    ], bump=course_batch.bump_seed)]
+    pub course_batch: Box<Account<'info, CourseBatch>>,
+    // expelled and withdrawn students keep their tokens frozen
+    #[account(has_one = student, has_one = course_batch,
+        constraint = matches!(
+            enrollment.status,
+            EnrollmentStatus::Active | EnrollmentStatus::Completed
+        ) @ CourseBatchManagerError::EnrollmentNotActive)]
+    pub enrollment: Box<Account<'info, Enrollment>>,
+    #[account(mut, has_one = course_batch)]
+    pub catalog_item: Box<Account<'info, CatalogItem>>,
+    // receipts of the item are numbered in the order of redemption
+    #[account(init, payer = student, space = 8 + Redemption::LEN, seeds=[
+        catalog_item.key().as_ref(),
+        REDEMPTION_SEED,
+        &catalog_item.redeemed.to_le_bytes(),
+    ], bump)]
+    pub redemption: Box<Account<'info, Redemption>>,
+    #[account(mut)]
+    pub mint: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = student,
+    )]
+    pub course_batch_token: Box<Account<'info, TokenAccount>>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'a, 'b, 'c, 'info> Redeem<'info> {
+    pub fn burn_cpi_ctx(&self) -> CpiContext<'a, 'b, 'c, 'info, Burn<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = Burn {
+            mint: self.mint.to_account_info(),
+            from: self.course_batch_token.to_account_info(),
+            authority: self.student.to_account_info(),
+        };
+        CpiContext::new(cpi_program, cpi_accounts)
+    }
+
+    pub fn freeze_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, FreezeAccount<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = FreezeAccount {
+            account: self.course_batch_token.to_account_info(),
+            mint: self.mint.to_account_info(),
+            authority: self.course_batch.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn thaw_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, ThawAccount<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = ThawAccount {
+            account: self.course_batch_token.to_account_info(),
+            mint: self.mint.to_account_info(),
+            authority: self.course_batch.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+pub struct FulfillRedemption<'info> {
+    pub authority: Signer<'info>,
+    #[account(constraint = course.authority == authority.key() @ CourseBatchManagerError::NotCourseAuthority)]
+    pub course: Account<'info, Course>,
+    #[account(has_one = course)]
+    pub course_batch: Account<'info, CourseBatch>,
+    #[account(has_one = course_batch)]
+    pub catalog_item: Account<'info, CatalogItem>,
+    #[account(mut, has_one = catalog_item)]
+    pub redemption: Account<'info, Redemption>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCourseBatch<'info> {
+    pub authority: Signer<'info>,
+    #[account(constraint = course.is_instructor(&authority.key()) @ CourseBatchManagerError::NotCourseInstructor)]
+    pub course: Account<'info, Course>,
+    #[account(mut, has_one = course)]
+    pub course_batch: Account<'info, CourseBatch>,
+}
+
+#[derive(Accounts)]
+pub struct EnrollBatch<'info> {
+    #[account(mut)]
+    pub student: Signer<'info>,
+    #[account(mut, has_one = mint,
+        seeds=[
+        COURSE_DATA_SEED,
+        course_batch.course.as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        BATCH_DATA_SEED,
+    ], bump=course_batch.bump_seed)]
+    pub course_batch: Account<'info, CourseBatch>,
+    #[account(init, payer = student, space = 8 + Enrollment::LEN, seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        BATCH_DATA_SEED,
+        course_batch.key().as_ref(),
+        ENROLLMENT_SEED,
+    ], bump)]
+    pub enrollment: Account<'info, Enrollment>,
+    #[account(mint::authority = course_batch)]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = student,
+        associated_token::mint = mint,
+        associated_token::authority = student,
+    )]
+    pub course_batch_token: Account<'info, TokenAccount>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>,
+    /// CHECK: instructions sysvar is read to verify signed invitations
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions: AccountInfo<'info>,
+}
+
+impl<'a, 'b, 'c, 'info> EnrollBatch<'info> {
+    pub fn freeze_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, FreezeAccount<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = FreezeAccount {
+            account: self.course_batch_token.to_account_info(),
+            mint: self.mint.to_account_info(),
+            authority: self.course_batch.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFromBatch<'info> {
+    pub student: Signer<'info>,
+    #[account(mut, has_one = mint,
+        seeds=[
+        COURSE_DATA_SEED,
+        course_batch.course.as_ref(),
+        BATCH_ID_SEED,
+        &course_batch.id,
+        BATCH_DATA_SEED,
+    ], bump=course_batch.bump_seed)]
+    pub course_batch: Account<'info, CourseBatch>,
+    #[account(mut, has_one = student, has_one = course_batch)]
+    pub enrollment: Account<'info, Enrollment>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = student,
+    )]
+    pub course_batch_token: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'a, 'b, 'c, 'info> WithdrawFromBatch<'info> {
+    pub fn freeze_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, FreezeAccount<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = FreezeAccount {
+            account: self.course_batch_token.to_account_info(),
+            mint: self.mint.to_account_info(),
+            authority: self.course_batch.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+pub struct ManageEnrollment<'info> {
+    pub authority: Signer<'info>,
+    #[account(constraint = course.authority == authority.key() @ CourseBatchManagerError::NotCourseAuthority)]
+    pub course: Account<'info, Course>,
+    #[account(mut, has_one = course, has_one = mint)]
     pub course_batch: Account<'info, CourseBatch>,
-    #[account(mint::authority = course_batch)]
+    #[account(mut, has_one = course_batch)]
+    pub enrollment: Account<'info, Enrollment>,
     pub mint: Account<'info, Mint>,
-    #[account(
-        init,
-        payer = student,
-        associated_token::mint = mint,
-        associated_token::authority = student,
-    )]
+    #[account(mut, address = get_associated_token_address(&enrollment.student, &mint.key()))]
     pub course_batch_token: Account<'info, TokenAccount>,
-    pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub rent: Sysvar<'info, Rent>,
+}
+
+impl<'a, 'b, 'c, 'info> ManageEnrollment<'info> {
+    pub fn freeze_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, FreezeAccount<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = FreezeAccount {
+            account: self.course_batch_token.to_account_info(),
+            mint: self.mint.to_account_info(),
+            authority: self.course_batch.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn thaw_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, ThawAccount<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = ThawAccount {
+            account: self.course_batch_token.to_account_info(),
+            mint: self.mint.to_account_info(),
+            authority: self.course_batch.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
 }
 
 #[derive(Accounts)]
-#[instruction(assignment_id: [u8; 16], hash_chain_length: u16)]
+#[instruction(assignment_id: [u8; 16], scope: Option<Pubkey>)]
 pub struct CreateAssignmentChecker<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
-    #[account(has_one = authority)]
+    #[account(constraint = course.is_instructor(&authority.key()) @ CourseBatchManagerError::NotCourseInstructor)]
     pub course: Account<'info, course_manager::Course>,
     // By default init sets the owner field of the created account to the currently executing program.
     // We override it to assignment_checker program.
     // Anchor will find the canonical bump for the assignment checker PDA derived for course_batch_manager program.
     // Course batch manager can sign for PDA to do mutable cross-program operations.
-    // The PDA is derived from course account and assignment IDs.
+    // The PDA is derived from course account and assignment IDs and the optional course batch scope.
     #[account(init, owner = assignment_checker_program.key(), payer = authority, space = 8 + AssignmentCheckerState::LEN, seeds=[
         COURSE_DATA_SEED,
         course.key().as_ref(),
         ASSIGNMENT_ID_SEED,
         assignment_id.as_ref(),
-    ], bump, constraint = hash_chain_length >= 2)]
+        scope_seed(&scope),
+    ], bump)]
     pub assignment_checker: Account<'info, AssignmentCheckerState>,
     pub assignment_checker_program: Program<'info, AssignmentChecker>,
     pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
@@ -354,11 +1784,87 @@ impl<'a, 'b, 'c, 'info> CreateAssignmentChecker<'info> {
 }
 
 #[derive(Accounts)]
-#[instruction(assignment_id: [u8; 16])]
+pub struct UpdateAssignmentChecker<'info> {
+    pub authority: Signer<'info>,
+    #[account(constraint = course.authority == authority.key() @ CourseBatchManagerError::NotCourseAuthority)]
+    pub course: Account<'info, Course>,
+    // CHECK: pda check will be made by assignment_checker
+    #[account(mut)]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+}
+
+impl<'a, 'b, 'c, 'info> UpdateAssignmentChecker<'info> {
+    pub fn update_checker_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, UpdateChecker<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = UpdateChecker {
+            authority: self.authority.to_account_info(),
+            course: self.course.to_account_info(),
+            assignment_checker: self.assignment_checker.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+pub struct CloseAssignmentChecker<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(constraint = course.authority == authority.key() @ CourseBatchManagerError::NotCourseAuthority)]
+    pub course: Account<'info, Course>,
+    // CHECK: pda check will be made by assignment_checker
+    #[account(mut)]
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+    /// CHECK: the rent payer saved on the checker is checked by assignment_checker
+    #[account(mut)]
+    pub rent_payer: UncheckedAccount<'info>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+}
+
+impl<'a, 'b, 'c, 'info> CloseAssignmentChecker<'info> {
+    pub fn close_checker_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, CloseChecker<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = CloseChecker {
+            authority: self.authority.to_account_info(),
+            course: self.course.to_account_info(),
+            assignment_checker: self.assignment_checker.to_account_info(),
+            rent_payer: self.rent_payer.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(assignment_id: [u8; 16], scope: Option<Pubkey>)]
 pub struct CreateCheckResult<'info> {
     #[account(mut)]
     pub student: Signer<'info>,
     pub course: Account<'info, course_manager::Course>,
+    #[account(has_one = course,
+        constraint = !matches!(scope, Some(scope) if scope != course_batch.key()) @ CourseBatchManagerError::CheckerScopeMismatch)]
+    pub course_batch: Account<'info, CourseBatch>,
+    #[account(has_one = student, has_one = course_batch,
+        constraint = enrollment.status == EnrollmentStatus::Active @ CourseBatchManagerError::EnrollmentNotActive,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        BATCH_DATA_SEED,
+        course_batch.key().as_ref(),
+        ENROLLMENT_SEED,
+    ], bump=enrollment.bump_seed)]
+    pub enrollment: Account<'info, Enrollment>,
 
     #[account(init, payer = student, space = 8 + assignment_checker::CheckResult::LEN,
         owner = assignment_checker::ID,
@@ -369,6 +1875,7 @@ pub struct CreateCheckResult<'info> {
         course.key().as_ref(),
         ASSIGNMENT_ID_SEED,
         assignment_id.as_ref(),
+        scope_seed(&scope),
     ], bump)]
     pub check_result: Account<'info, assignment_checker::CheckResult>,
     pub assignment_checker_program: Program<'info, AssignmentChecker>,
@@ -397,7 +1904,7 @@ pub struct CheckAssignment<'info> {
     #[account(mut)]
     pub student: Signer<'info>,
     pub course: Account<'info, Course>,
-    #[account(has_one = mint, constraint = course.authority == course_batch.authority,
+    #[account(has_one = mint, has_one = course,
         seeds=[
         COURSE_DATA_SEED,
         course.key().as_ref(),
@@ -406,8 +1913,20 @@ pub struct CheckAssignment<'info> {
         BATCH_DATA_SEED,
     ], bump=course_batch.bump_seed)]
     pub course_batch: Account<'info, CourseBatch>,
+    #[account(mut, has_one = student, has_one = course_batch,
+        constraint = enrollment.status == EnrollmentStatus::Active @ CourseBatchManagerError::EnrollmentNotActive,
+        seeds=[
+        STUDENT_ADDRESS_SEED,
+        student.key().as_ref(),
+        BATCH_DATA_SEED,
+        course_batch.key().as_ref(),
+        ENROLLMENT_SEED,
+    ], bump=enrollment.bump_seed)]
+    pub enrollment: Account<'info, Enrollment>,
     // CHECK: pda check and assignment_id equality will be made by assignment_checker
-    #[account(mut)]
+    // a batch-scoped checker mints only into its own batch
+    #[account(mut,
+        constraint = !matches!(assignment_checker.scope, Some(scope) if scope != course_batch.key()) @ CourseBatchManagerError::CheckerScopeMismatch)]
     pub assignment_checker: Account<'info, AssignmentCheckerState>,
 
     // CHECK: pda check and assignment_id equality will be made by assignment_checker
@@ -435,6 +1954,123 @@ pub struct CheckAssignment<'info> {
     pub token_program: Program<'info, Token>,
     pub assignment_checker_program: Program<'info, AssignmentChecker>,
     pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+    /// CHECK: instructions sysvar is read by assignment_checker to verify grader attestations
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions: AccountInfo<'info>,
+    /// CHECK: the registry is optional, the reputation is updated only when it exists
+    #[account(seeds = [reputation::CONFIG_SEED], bump, seeds::program = reputation_program.key())]
+    pub reputation_config: UncheckedAccount<'info>,
+    /// CHECK: reputation of the student is optional, it is updated only when the account exists
+    #[account(mut)]
+    pub reputation: UncheckedAccount<'info>,
+    /// CHECK: signer PDA of the program recognised by the reputation program
+    #[account(seeds = [reputation::WRITER_SEED], bump)]
+    pub reputation_writer: UncheckedAccount<'info>,
+    pub reputation_program: Program<'info, ReputationProgram>,
+}
+
+impl<'info> CheckAssignment<'info> {
+    fn require_active_batch(&self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        if !self.course_batch.schedule.is_active(now) {
+            return Err(error!(CourseBatchManagerError::BatchNotActive));
+        }
+        Ok(())
+    }
+
+    /// Mint the reward of the assignment checker when the check has passed the first time
+    ///
+    /// Multi-part checkers mint the points of the parts passed by this check,
+    /// `parts_passed_before` is the bitmap of passed parts before the check.
+    /// Quiz checkers mint the reward proportional to the score.
+    /// Late solutions get the reward reduced according to the checker reward schedule.
+    /// Token account of a soulbound batch is thawed only for the time of minting.
+    /// The minted amount is added to the cross-course reputation of the student when it exists.
+    fn mint_on_first_pass(
+        &mut self,
+        parts_passed_before: u16,
+        reputation_writer_bump: u8,
+    ) -> Result<()> {
+        // deserialize check_result again after assignment checker has changed the account
+        self.check_result.reload()?;
+
+        let check_result = &self.check_result;
+        msg!(
+            "check_passed: {}, passed_first_time: {}",
+            check_result.check_passed,
+            check_result.passed_first_time
+        );
+        let passed_first_time = check_result.check_passed && check_result.passed_first_time;
+        let now = Clock::get()?.unix_timestamp;
+        let amount: u64 = match self.assignment_checker.kind {
+            CheckerKind::MultiPart => {
+                let newly_passed_parts = check_result.parts_passed & !parts_passed_before;
+                self.assignment_checker
+                    .parts_reward_at(newly_passed_parts, now)
+                    .into()
+            }
+            CheckerKind::Quiz { .. } if passed_first_time => self
+                .assignment_checker
+                .quiz_reward(check_result.score)
+                .into(),
+            CheckerKind::GraderAttestation { .. } if passed_first_time => self
+                .assignment_checker
+                .attested_reward_at(check_result.score, now)
+                .into(),
+            _ if passed_first_time => self.assignment_checker.reward_at(now).into(),
+            _ => 0,
+        };
+        if passed_first_time {
+            self.enrollment.assignments_passed += 1;
+        }
+        if amount > 0 {
+            let course_key = self.course.key();
+            let mint_seeds = [
+                COURSE_DATA_SEED,
+                course_key.as_ref(),
+                BATCH_ID_SEED,
+                self.course_batch.id.as_ref(),
+                BATCH_MINT_SEED,
+                &[self.course_batch.mint_bump_seed],
+            ];
+            let course_batch_seeds = [
+                COURSE_DATA_SEED,
+                course_key.as_ref(),
+                BATCH_ID_SEED,
+                self.course_batch.id.as_ref(),
+                BATCH_DATA_SEED,
+                &[self.course_batch.bump_seed],
+            ];
+            let signer_seeds = [mint_seeds.as_slice(), course_batch_seeds.as_slice()];
+            if self.course_batch.soulbound {
+                thaw_account(self.thaw_cpi_ctx(signer_seeds.as_slice()))?;
+            }
+            mint_to(self.mint_to_cpi_ctx(signer_seeds.as_slice()), amount)?;
+            if self.course_batch.soulbound {
+                freeze_account(self.freeze_cpi_ctx(signer_seeds.as_slice()))?;
+            }
+            msg!("minted {} tokens to {}", amount, self.student.key());
+
+            self.enrollment.tokens_earned += amount;
+
+            let reputation_writer_seeds =
+                [reputation::WRITER_SEED.as_ref(), &[reputation_writer_bump]];
+            let signer_seeds = [reputation_writer_seeds.as_slice()];
+            if can_record_reputation(
+                &self.reputation_config,
+                &self.reputation,
+                &self.reputation_writer.key(),
+                &self.student.key(),
+            )? {
+                reputation::cpi::record_points(
+                    self.record_cpi_ctx(signer_seeds.as_slice()),
+                    course_key,
+                    amount,
+                )?;
+            }
+        }
+        Ok(())
+    }
 }
 
 impl<'a, 'b, 'c, 'info> CheckAssignment<'info> {
@@ -452,6 +2088,21 @@ impl<'a, 'b, 'c, 'info> CheckAssignment<'info> {
             assignment_checker: self.assignment_checker.to_account_info(),
             check_result,
             result_processor_program: self.course_batch_manager_program.to_account_info(),
+            instructions: self.instructions.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn record_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, Record<'info>> {
+        let cpi_program = self.reputation_program.to_account_info();
+
+        let cpi_accounts = Record {
+            writer: self.reputation_writer.to_account_info(),
+            config: self.reputation_config.to_account_info(),
+            reputation: self.reputation.to_account_info(),
         };
         CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
     }
@@ -469,7 +2120,66 @@ impl<'a, 'b, 'c, 'info> CheckAssignment<'info> {
         };
         CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
     }
+
+    pub fn freeze_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, FreezeAccount<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = FreezeAccount {
+            account: self.course_batch_token.to_account_info(),
+            mint: self.mint.to_account_info(),
+            authority: self.course_batch.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+
+    pub fn thaw_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, ThawAccount<'info>> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = ThawAccount {
+            account: self.course_batch_token.to_account_info(),
+            mint: self.mint.to_account_info(),
+            authority: self.course_batch.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
+}
+#[derive(Accounts)]
+pub struct CommitAssignment<'info> {
+    pub student: Signer<'info>,
+    pub course: Account<'info, Course>,
+    // CHECK: pda check and assignment_id equality will be made by assignment_checker
+    pub assignment_checker: Account<'info, AssignmentCheckerState>,
+    // CHECK: pda check will be made by assignment_checker
+    #[account(mut)]
+    pub check_result: Account<'info, CheckResult>,
+    pub assignment_checker_program: Program<'info, AssignmentChecker>,
+    pub course_batch_manager_program: Program<'info, program::CourseBatchManager>,
+}
+
+impl<'a, 'b, 'c, 'info> CommitAssignment<'info> {
+    pub fn commit_cpi_ctx(
+        &self,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> CpiContext<'a, 'b, 'c, 'info, Commit<'info>> {
+        let cpi_program = self.assignment_checker_program.to_account_info();
+
+        let cpi_accounts = Commit {
+            student: self.student.to_account_info(),
+            course: self.course.to_account_info(),
+            assignment_checker: self.assignment_checker.to_account_info(),
+            check_result: self.check_result.to_account_info(),
+            result_processor_program: self.course_batch_manager_program.to_account_info(),
+        };
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds)
+    }
 }
+
 /// Each batch of any course has unique id
 ///
 /// Course authority creates course batch account on each batch of course.
@@ -484,8 +2194,286 @@ pub struct CourseBatch {
     pub mint: Pubkey,
     pub bump_seed: u8,
     pub mint_bump_seed: u8,
+    /// Version of the course syllabus the batch runs
+    pub syllabus_version: u32,
+    pub schedule: BatchSchedule,
+    pub enrollment_policy: EnrollmentPolicy,
+    /// Number of students enrolled the batch
+    pub enrolled_count: u32,
+    /// Batch tokens can't be transferred between students
+    pub soulbound: bool,
 }
 
 impl CourseBatch {
-    pub const LEN: usize = 16 + PUBKEY_BYTES * 3 + 1 + 1;
+    pub const LEN: usize =
+        16 + PUBKEY_BYTES * 3 + 1 + 1 + 4 + BatchSchedule::LEN + EnrollmentPolicy::LEN + 4 + 1;
+}
+
+/// Unix timestamps of the course batch periods
+///
+/// Each period includes its start and excludes its end
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct BatchSchedule {
+    /// Students can enroll the batch since this moment
+    pub enrollment_opens_at: i64,
+    pub enrollment_closes_at: i64,
+    /// Students can check assignments since this moment
+    pub starts_at: i64,
+    pub ends_at: i64,
+}
+
+impl BatchSchedule {
+    pub const LEN: usize = 8 * 4;
+
+    pub fn is_enrollment_open(&self, now: i64) -> bool {
+        self.enrollment_opens_at <= now && now < self.enrollment_closes_at
+    }
+
+    pub fn is_active(&self, now: i64) -> bool {
+        self.starts_at <= now && now < self.ends_at
+    }
+}
+
+/// Record of a student enrolled into a course batch
+#[account]
+pub struct Enrollment {
+    pub student: Pubkey,
+    pub course_batch: Pubkey,
+    /// Unix timestamp of the enrollment
+    pub joined_at: i64,
+    pub status: EnrollmentStatus,
+    pub bump_seed: u8,
+    /// Number of assignments passed in the batch
+    pub assignments_passed: u32,
+    /// Number of batch tokens minted for the passed assignments
+    pub tokens_earned: u64,
+}
+
+impl Enrollment {
+    pub const LEN: usize = PUBKEY_BYTES * 2 + 8 + 1 + 1 + 4 + 8;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EnrollmentStatus {
+    /// The student can solve and check assignments
+    Active,
+    /// The student has left the batch
+    Withdrawn,
+    /// The student has finished the batch
+    Completed,
+    /// The course instructors have removed the student from the batch
+    Expelled,
+}
+
+/// Reward of the course batch catalog
+#[account]
+pub struct CatalogItem {
+    pub course_batch: Pubkey,
+    pub id: [u8; 16],
+    /// Number of batch tokens burned on redemption
+    pub cost: u64,
+    /// Max number of redemptions
+    pub supply: u32,
+    pub redeemed: u32,
+    /// URI of the off-chain reward description
+    pub reward_uri: String,
+    pub bump_seed: u8,
+}
+
+impl CatalogItem {
+    pub const LEN: usize = PUBKEY_BYTES + 16 + 8 + 4 + 4 + (4 + MAX_REWARD_URI_LEN) + 1;
+}
+
+/// Receipt of a redeemed catalog item
+#[account]
+pub struct Redemption {
+    pub catalog_item: Pubkey,
+    pub student: Pubkey,
+    /// Number of batch tokens burned
+    pub cost: u64,
+    /// Unix timestamp of the redemption
+    pub redeemed_at: i64,
+    /// Unix timestamp when the reward was handed over
+    pub fulfilled_at: Option<i64>,
+    pub bump_seed: u8,
+}
+
+impl Redemption {
+    pub const LEN: usize = PUBKEY_BYTES * 2 + 8 + 8 + (1 + 8) + 1;
+}
+
+/// What students of the course batch should achieve to get the certificate
+#[account]
+pub struct CertificatePolicy {
+    pub course_batch: Pubkey,
+    pub requirement: CertificateRequirement,
+    /// URI of the off-chain JSON of the certificate NFT
+    pub uri: String,
+    pub bump_seed: u8,
+}
+
+impl CertificatePolicy {
+    pub const LEN: usize =
+        PUBKEY_BYTES + CertificateRequirement::LEN + (4 + MAX_CERTIFICATE_URI_LEN) + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub enum CertificateRequirement {
+    /// The student has earned at least `amount` batch tokens in the batch,
+    /// tokens received from other students don't count
+    MinTokens { amount: u64 },
+    /// The student has passed all the assignments
+    PassedAssignments { assignment_ids: Vec<[u8; 16]> },
+}
+
+impl CertificateRequirement {
+    pub const LEN: usize = 1 + 4 + 16 * MAX_REQUIRED_ASSIGNMENTS;
+}
+
+/// Record of the certificate issued to a student of a course batch
+#[account]
+pub struct Certificate {
+    pub course_batch: Pubkey,
+    pub student: Pubkey,
+    /// Mint of the certificate NFT
+    pub mint: Pubkey,
+    /// Batch tokens earned by the student
+    pub final_score: u64,
+    /// Unix timestamp of the claim
+    pub issued_at: i64,
+}
+
+impl Certificate {
+    pub const LEN: usize = PUBKEY_BYTES * 3 + 8 + 8;
+}
+
+/// Who and how many students can enroll the course batch
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct EnrollmentPolicy {
+    /// Seat limit, unlimited when not set
+    pub max_seats: Option<u32>,
+    pub allow_list: AllowList,
+}
+
+impl EnrollmentPolicy {
+    pub const LEN: usize = (1 + 4) + AllowList::LEN;
+}
+
+impl Default for EnrollmentPolicy {
+    fn default() -> Self {
+        EnrollmentPolicy {
+            max_seats: None,
+            allow_list: AllowList::Open,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AllowList {
+    /// Any student can enroll
+    Open,
+    /// Merkle root of `allow_list_leaf(course_batch, student)` leaves
+    MerkleRoot { root: [u8; 32] },
+    /// Students present `invitation_message(course_batch, student)` signed by the inviter
+    Invitations { inviter: Pubkey },
+}
+
+impl AllowList {
+    pub const LEN: usize = 1 + 32;
+}
+
+#[error_code]
+pub enum CourseBatchManagerError {
+    #[msg("The signer is neither the course authority nor a co-instructor")]
+    NotCourseInstructor,
+    #[msg("The signer is not the course authority")]
+    NotCourseAuthority,
+    #[msg("Batch periods should not end before they start")]
+    InvalidSchedule,
+    #[msg("Enrollment into the batch is closed")]
+    EnrollmentClosed,
+    #[msg("The batch has not started yet or has already ended")]
+    BatchNotActive,
+    #[msg("All seats of the batch are taken")]
+    BatchFull,
+    #[msg("The student is not on the allow-list of the batch")]
+    NotInvited,
+    #[msg("The seat limit is lower than the number of enrolled students")]
+    SeatsBelowEnrollment,
+    #[msg("The student is not actively enrolled into the batch")]
+    EnrollmentNotActive,
+    #[msg("The student has neither withdrawn nor been expelled from the batch")]
+    NotRemovedFromBatch,
+    #[msg("A certificate can require at most 16 assignments")]
+    TooManyRequiredAssignments,
+    #[msg("Certificate URI is longer than 200 bytes")]
+    CertificateUriTooLong,
+    #[msg("The student has not met the certificate requirement")]
+    CertificateRequirementNotMet,
+    #[msg("Reward URI is longer than 200 bytes")]
+    RewardUriTooLong,
+    #[msg("The whole supply of the catalog item is redeemed")]
+    CatalogItemSoldOut,
+    #[msg("The redemption is already fulfilled")]
+    RedemptionAlreadyFulfilled,
+    #[msg("The assignment checker is limited to another course batch")]
+    CheckerScopeMismatch,
+    #[msg("The reputation account belongs to another student")]
+    ReputationStudentMismatch,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEDULE: BatchSchedule = BatchSchedule {
+        enrollment_opens_at: 1_000,
+        enrollment_closes_at: 2_000,
+        starts_at: 1_500,
+        ends_at: 3_000,
+    };
+
+    #[test]
+    fn enrollment_window_includes_its_start_and_excludes_its_end() {
+        assert!(!SCHEDULE.is_enrollment_open(999));
+        assert!(SCHEDULE.is_enrollment_open(1_000));
+        assert!(SCHEDULE.is_enrollment_open(1_999));
+        assert!(!SCHEDULE.is_enrollment_open(2_000));
+    }
+
+    #[test]
+    fn batch_period_includes_its_start_and_excludes_its_end() {
+        assert!(!SCHEDULE.is_active(1_499));
+        assert!(SCHEDULE.is_active(1_500));
+        assert!(SCHEDULE.is_active(2_999));
+        assert!(!SCHEDULE.is_active(3_000));
+    }
+
+    #[test]
+    fn empty_periods_are_never_open() {
+        let schedule = BatchSchedule {
+            enrollment_opens_at: 1_000,
+            enrollment_closes_at: 1_000,
+            starts_at: 1_000,
+            ends_at: 1_000,
+        };
+        for now in [999, 1_000, 1_001] {
+            assert!(!schedule.is_enrollment_open(now));
+            assert!(!schedule.is_active(now));
+        }
+    }
+
+    #[test]
+    fn open_ended_schedule() {
+        let schedule = BatchSchedule {
+            enrollment_opens_at: i64::MIN,
+            enrollment_closes_at: i64::MAX,
+            starts_at: i64::MIN,
+            ends_at: i64::MAX,
+        };
+        assert!(schedule.is_enrollment_open(i64::MIN));
+        assert!(schedule.is_active(0));
+        assert!(!schedule.is_enrollment_open(i64::MAX));
+        assert!(!schedule.is_active(i64::MAX));
+    }
 }
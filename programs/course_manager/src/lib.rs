@@ -5,6 +5,10 @@ declare_id!("Po2hjSPEQmN9e1YLiZwwL3tCkqMCo2wYyLqAkF7ZmQn");
 
 pub const COURSE_AUTHORITY_SEED: &[u8; 16] = b"course_authority";
 pub const COURSE_ID_SEED: &[u8; 9] = b"course_id";
+pub const COURSE_METADATA_SEED: &[u8; 15] = b"course_metadata";
+pub const MAX_CO_INSTRUCTORS: usize = 4;
+pub const MAX_TITLE_LEN: usize = 64;
+pub const MAX_CONTENT_URI_LEN: usize = 200;
 
 #[program]
 pub mod course_manager {
@@ -15,6 +19,99 @@ pub mod course_manager {
         course_account.id = course_id;
         course_account.authority = ctx.accounts.course_authority.key();
         course_account.bump_seed = *ctx.bumps.get("course").expect("course pda is present");
+
+        // metadata is published later by update_course_metadata
+        let metadata_account = &mut ctx.accounts.course_metadata;
+        metadata_account.course = course_account.key();
+        metadata_account.bump_seed = *ctx
+            .bumps
+            .get("course_metadata")
+            .expect("course_metadata pda is present");
+        Ok(())
+    }
+
+    /// Publish new version of the course syllabus
+    ///
+    /// `version` should be greater than the current one.
+    /// Batches created after the update run this version.
+    pub fn update_course_metadata(
+        ctx: Context<UpdateCourseMetadata>,
+        title: String,
+        content_uri: String,
+        content_hash: [u8; 32],
+        version: u32,
+    ) -> Result<()> {
+        if title.len() > MAX_TITLE_LEN {
+            return Err(error!(CourseError::TitleTooLong));
+        }
+        if content_uri.len() > MAX_CONTENT_URI_LEN {
+            return Err(error!(CourseError::ContentUriTooLong));
+        }
+        let metadata_account = &mut ctx.accounts.course_metadata;
+        if version <= metadata_account.version {
+            return Err(error!(CourseError::VersionNotIncreased));
+        }
+        metadata_account.title = title;
+        metadata_account.content_uri = content_uri;
+        metadata_account.content_hash = content_hash;
+        metadata_account.version = version;
+        msg!("course metadata version {} is published", version);
+        Ok(())
+    }
+
+    /// Propose new course authority
+    ///
+    /// The transfer is completed when the proposed authority calls `accept_authority`.
+    /// A new proposal replaces the previous one.
+    pub fn propose_authority(ctx: Context<UpdateCourse>, new_authority: Pubkey) -> Result<()> {
+        ctx.accounts.course.pending_authority = Some(new_authority);
+        Ok(())
+    }
+
+    /// Withdraw the proposal of new course authority before it is accepted
+    pub fn cancel_authority_transfer(ctx: Context<UpdateCourse>) -> Result<()> {
+        let course_account = &mut ctx.accounts.course;
+        if course_account.pending_authority.take().is_none() {
+            return Err(error!(CourseError::NoPendingAuthority));
+        }
+        msg!("course authority transfer is cancelled");
+        Ok(())
+    }
+
+    /// Become the course authority proposed by the current one
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let course_account = &mut ctx.accounts.course;
+        let new_authority = ctx.accounts.new_authority.key();
+        course_account.authority = new_authority;
+        course_account.pending_authority = None;
+        // the authority has all permissions of a co-instructor
+        course_account
+            .co_instructors
+            .retain(|co_instructor| *co_instructor != new_authority);
+        msg!("course authority is transferred to {}", new_authority);
+        Ok(())
+    }
+
+    /// Allow co-instructor to create batches and assignment checkers of the course
+    pub fn add_co_instructor(ctx: Context<UpdateCourse>, co_instructor: Pubkey) -> Result<()> {
+        let course_account = &mut ctx.accounts.course;
+        if course_account.is_instructor(&co_instructor) {
+            return Err(error!(CourseError::AlreadyInstructor));
+        }
+        if course_account.co_instructors.len() >= MAX_CO_INSTRUCTORS {
+            return Err(error!(CourseError::TooManyCoInstructors));
+        }
+        course_account.co_instructors.push(co_instructor);
+        Ok(())
+    }
+
+    pub fn remove_co_instructor(ctx: Context<UpdateCourse>, co_instructor: Pubkey) -> Result<()> {
+        let co_instructors = &mut ctx.accounts.course.co_instructors;
+        let index = co_instructors
+            .iter()
+            .position(|key| *key == co_instructor)
+            .ok_or_else(|| error!(CourseError::CoInstructorNotFound))?;
+        co_instructors.remove(index);
         Ok(())
     }
 }
@@ -32,6 +129,10 @@ pub fn course_canonical_pda(course_authority: Pubkey, course_id: &[u8; 16]) -> P
     .0
 }
 
+pub fn course_metadata_canonical_pda(course_address: Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[COURSE_METADATA_SEED, course_address.as_ref()], &ID).0
+}
+
 #[derive(Accounts)]
 #[instruction(course_id: [u8; 16])]
 pub struct NewCourse<'info> {
@@ -48,9 +149,37 @@ pub struct NewCourse<'info> {
         &course_id
     ], bump)]
     pub course: Account<'info, Course>,
+    #[account(init, payer = course_authority, space = 8 + CourseMetadata::LEN, seeds=[
+        COURSE_METADATA_SEED,
+        course.key().as_ref(),
+    ], bump)]
+    pub course_metadata: Account<'info, CourseMetadata>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct UpdateCourseMetadata<'info> {
+    pub authority: Signer<'info>,
+    #[account(has_one = authority)]
+    pub course: Account<'info, Course>,
+    #[account(mut, has_one = course)]
+    pub course_metadata: Account<'info, CourseMetadata>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCourse<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority)]
+    pub course: Account<'info, Course>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    pub new_authority: Signer<'info>,
+    #[account(mut, constraint = course.pending_authority == Some(new_authority.key()) @ CourseError::NotProposedAuthority)]
+    pub course: Account<'info, Course>,
+}
+
 /// Each course has unique id
 ///
 /// Course authority creates course account before the first batch of new course.
@@ -61,8 +190,59 @@ pub struct Course {
     /// Course organizer
     pub authority: Pubkey,
     pub bump_seed: u8,
+    /// Authority proposed by the current one, it should accept the course
+    pub pending_authority: Option<Pubkey>,
+    /// Instructors who can create batches and assignment checkers
+    /// but cannot transfer the course
+    pub co_instructors: Vec<Pubkey>,
 }
 
 impl Course {
-    pub const LEN: usize = 16 + PUBKEY_BYTES + 1;
+    pub const LEN: usize =
+        16 + PUBKEY_BYTES + 1 + (1 + PUBKEY_BYTES) + (4 + PUBKEY_BYTES * MAX_CO_INSTRUCTORS);
+
+    /// Whether the key is the course authority or a co-instructor
+    pub fn is_instructor(&self, key: &Pubkey) -> bool {
+        self.authority == *key || self.co_instructors.contains(key)
+    }
+}
+
+/// Syllabus of the course shown by explorers and frontends
+///
+/// The content itself is kept off-chain at `content_uri`
+#[account]
+pub struct CourseMetadata {
+    pub course: Pubkey,
+    pub title: String,
+    pub content_uri: String,
+    /// Hash of the content at `content_uri`
+    pub content_hash: [u8; 32],
+    /// Syllabus version, 0 until the first update
+    pub version: u32,
+    pub bump_seed: u8,
+}
+
+impl CourseMetadata {
+    pub const LEN: usize =
+        PUBKEY_BYTES + (4 + MAX_TITLE_LEN) + (4 + MAX_CONTENT_URI_LEN) + 32 + 4 + 1;
+}
+
+#[error_code]
+pub enum CourseError {
+    #[msg("The signer is not the proposed course authority")]
+    NotProposedAuthority,
+    #[msg("No course authority is proposed")]
+    NoPendingAuthority,
+    #[msg("The key is already an instructor of the course")]
+    AlreadyInstructor,
+    #[msg("The course has maximal number of co-instructors")]
+    TooManyCoInstructors,
+    #[msg("The key is not a co-instructor of the course")]
+    CoInstructorNotFound,
+    #[msg("Course title is too long")]
+    TitleTooLong,
+    #[msg("Course content URI is too long")]
+    ContentUriTooLong,
+    #[msg("Course metadata version should be greater than the current one")]
+    VersionNotIncreased,
 }
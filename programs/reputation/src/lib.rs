@@ -0,0 +1,311 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{bpf_loader_upgradeable, pubkey::PUBKEY_BYTES};
+
+declare_id!("Po4tUS1J8E4w1tLKHt2UCUsDjVwQ4FzqwxVC6pbJqia");
+
+pub const REPUTATION_SEED: &[u8; 10] = b"reputation";
+pub const CONFIG_SEED: &[u8; 17] = b"reputation_config";
+/// Seed of the PDA a writer program signs reputation updates with
+pub const WRITER_SEED: &[u8; 17] = b"reputation_writer";
+pub const MAX_WRITERS: usize = 8;
+pub const MAX_COURSES: usize = 32;
+
+#[program]
+pub mod reputation {
+    use super::*;
+
+    /// Create the registry of programs allowed to update reputations
+    ///
+    /// Only the upgrade authority of the program can create it and become the registry authority
+    pub fn init_config(ctx: Context<InitConfig>) -> Result<()> {
+        let config_account = &mut ctx.accounts.config;
+        config_account.authority = ctx.accounts.authority.key();
+        config_account.bump_seed = *ctx.bumps.get("config").expect("config pda is present");
+        Ok(())
+    }
+
+    /// Allow the program to update reputations
+    ///
+    /// The program signs updates with its `writer_signer_address`
+    pub fn add_writer(ctx: Context<UpdateConfig>, writer_program: Pubkey) -> Result<()> {
+        let config_account = &mut ctx.accounts.config;
+        let writer = writer_signer_address(&writer_program);
+        if config_account.writers.contains(&writer) {
+            return Err(error!(ReputationError::AlreadyWriter));
+        }
+        if config_account.writers.len() >= MAX_WRITERS {
+            return Err(error!(ReputationError::TooManyWriters));
+        }
+        config_account.writers.push(writer);
+        Ok(())
+    }
+
+    pub fn remove_writer(ctx: Context<UpdateConfig>, writer_program: Pubkey) -> Result<()> {
+        let config_account = &mut ctx.accounts.config;
+        let writer = writer_signer_address(&writer_program);
+        let position = config_account
+            .writers
+            .iter()
+            .position(|key| *key == writer)
+            .ok_or_else(|| error!(ReputationError::WriterNotFound))?;
+        config_account.writers.remove(position);
+        Ok(())
+    }
+
+    /// Create the reputation account of the student, anybody can pay for it
+    pub fn init_reputation(ctx: Context<InitReputation>, student: Pubkey) -> Result<()> {
+        let reputation_account = &mut ctx.accounts.reputation;
+        reputation_account.student = student;
+        reputation_account.bump_seed = *ctx
+            .bumps
+            .get("reputation")
+            .expect("reputation pda is present");
+        Ok(())
+    }
+
+    /// Add points the student has earned in the course
+    pub fn record_points(ctx: Context<Record>, course: Pubkey, points: u64) -> Result<()> {
+        ctx.accounts.reputation.add_points(course, points);
+        Ok(())
+    }
+
+    /// Count the course as completed by the student, repeated completions are ignored
+    pub fn record_course_completed(ctx: Context<Record>, course: Pubkey) -> Result<()> {
+        ctx.accounts.reputation.complete_course(course);
+        Ok(())
+    }
+}
+
+/// PDA of the writer program which signs reputation updates
+pub fn writer_signer_address(writer_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[WRITER_SEED], writer_program).0
+}
+
+pub fn config_canonical_pda() -> Pubkey {
+    Pubkey::find_program_address(&[CONFIG_SEED], &ID).0
+}
+
+pub fn reputation_canonical_pda(student: Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[REPUTATION_SEED, student.as_ref()], &ID).0
+}
+
+/// Account of the upgradeable loader keeping the upgrade authority of the program
+pub fn program_data_address() -> Pubkey {
+    Pubkey::find_program_address(&[ID.as_ref()], &bpf_loader_upgradeable::ID).0
+}
+
+#[derive(Accounts)]
+pub struct InitConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(init, payer = authority, space = 8 + Config::LEN, seeds = [CONFIG_SEED], bump)]
+    pub config: Account<'info, Config>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, program::Reputation>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(authority.key()) @ ReputationError::NotUpgradeAuthority)]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority, seeds = [CONFIG_SEED], bump = config.bump_seed)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+#[instruction(student: Pubkey)]
+pub struct InitReputation<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(init, payer = payer, space = 8 + Reputation::LEN, seeds = [
+        REPUTATION_SEED,
+        student.as_ref(),
+    ], bump)]
+    pub reputation: Account<'info, Reputation>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Record<'info> {
+    #[account(constraint = config.writers.contains(&writer.key()) @ ReputationError::NotWriter)]
+    pub writer: Signer<'info>,
+    #[account(seeds = [CONFIG_SEED], bump = config.bump_seed)]
+    pub config: Account<'info, Config>,
+    #[account(mut, seeds = [
+        REPUTATION_SEED,
+        reputation.student.as_ref(),
+    ], bump = reputation.bump_seed)]
+    pub reputation: Account<'info, Reputation>,
+}
+
+/// Registry of programs allowed to update reputations
+#[account]
+pub struct Config {
+    pub authority: Pubkey,
+    /// Signer PDAs of the writer programs
+    pub writers: Vec<Pubkey>,
+    pub bump_seed: u8,
+}
+
+impl Config {
+    pub const LEN: usize = PUBKEY_BYTES + (4 + PUBKEY_BYTES * MAX_WRITERS) + 1;
+}
+
+/// Achievements of a student across all courses
+#[account]
+pub struct Reputation {
+    pub student: Pubkey,
+    /// Batch tokens earned in all courses
+    pub total_points: u64,
+    pub courses_completed: u32,
+    pub courses: Vec<CourseReputation>,
+    pub bump_seed: u8,
+}
+
+impl Reputation {
+    pub const LEN: usize = PUBKEY_BYTES + 8 + 4 + (4 + CourseReputation::LEN * MAX_COURSES) + 1;
+
+    /// Points are added to the total even when the course list is full
+    fn add_points(&mut self, course: Pubkey, points: u64) {
+        self.total_points = self.total_points.saturating_add(points);
+        match self.course_mut(course) {
+            Some(course_reputation) => {
+                course_reputation.points = course_reputation.points.saturating_add(points)
+            }
+            None => msg!(
+                "course list is full, {} points are not listed by course",
+                points
+            ),
+        }
+    }
+
+    /// The completion is ignored when the course list is full, repeated completions can't be told apart
+    fn complete_course(&mut self, course: Pubkey) {
+        match self.course_mut(course) {
+            Some(course_reputation) if !course_reputation.completed => {
+                course_reputation.completed = true;
+                self.courses_completed += 1;
+            }
+            Some(_) => {}
+            None => msg!(
+                "course list is full, completion of {} is not recorded",
+                course
+            ),
+        }
+    }
+
+    /// Reputation in the course, the course is added unless the list is full
+    fn course_mut(&mut self, course: Pubkey) -> Option<&mut CourseReputation> {
+        let position = match self.courses.iter().position(|item| item.course == course) {
+            Some(position) => position,
+            None if self.courses.len() < MAX_COURSES => {
+                self.courses.push(CourseReputation {
+                    course,
+                    points: 0,
+                    completed: false,
+                });
+                self.courses.len() - 1
+            }
+            None => return None,
+        };
+        Some(&mut self.courses[position])
+    }
+}
+
+/// Achievements of a student in a single course
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct CourseReputation {
+    pub course: Pubkey,
+    pub points: u64,
+    pub completed: bool,
+}
+
+impl CourseReputation {
+    pub const LEN: usize = PUBKEY_BYTES + 8 + 1;
+}
+
+#[error_code]
+pub enum ReputationError {
+    #[msg("The signer is not a registered reputation writer")]
+    NotWriter,
+    #[msg("The program is already a reputation writer")]
+    AlreadyWriter,
+    #[msg("The registry has maximal number of writers")]
+    TooManyWriters,
+    #[msg("The program is not a reputation writer")]
+    WriterNotFound,
+    #[msg("The signer is not the upgrade authority of the program")]
+    NotUpgradeAuthority,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reputation() -> Reputation {
+        Reputation {
+            student: Pubkey::new_unique(),
+            total_points: 0,
+            courses_completed: 0,
+            courses: vec![],
+            bump_seed: 255,
+        }
+    }
+
+    #[test]
+    fn points_are_added_by_course() {
+        let mut reputation = reputation();
+        let (course_a, course_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        reputation.add_points(course_a, 100);
+        reputation.add_points(course_b, 50);
+        reputation.add_points(course_a, 20);
+        assert_eq!(reputation.total_points, 170);
+        assert_eq!(reputation.courses.len(), 2);
+        assert_eq!(reputation.courses[0].points, 120);
+        assert_eq!(reputation.courses[1].points, 50);
+    }
+
+    #[test]
+    fn repeated_completion_is_counted_once() {
+        let mut reputation = reputation();
+        let course = Pubkey::new_unique();
+        reputation.complete_course(course);
+        reputation.complete_course(course);
+        assert_eq!(reputation.courses_completed, 1);
+        assert!(reputation.courses[0].completed);
+    }
+
+    #[test]
+    fn full_course_list_does_not_fail_records() {
+        let mut reputation = reputation();
+        for _ in 0..MAX_COURSES {
+            reputation.add_points(Pubkey::new_unique(), 1);
+        }
+        let listed_course = reputation.courses[0].course;
+
+        let course = Pubkey::new_unique();
+        reputation.add_points(course, 10);
+        reputation.complete_course(course);
+        assert_eq!(reputation.courses.len(), MAX_COURSES);
+        assert_eq!(reputation.total_points, MAX_COURSES as u64 + 10);
+        assert_eq!(reputation.courses_completed, 0);
+
+        // listed courses are still updated
+        reputation.add_points(listed_course, 5);
+        reputation.complete_course(listed_course);
+        assert_eq!(reputation.courses[0].points, 6);
+        assert_eq!(reputation.courses_completed, 1);
+    }
+
+    #[test]
+    fn points_saturate() {
+        let mut reputation = reputation();
+        let course = Pubkey::new_unique();
+        reputation.add_points(course, u64::MAX);
+        reputation.add_points(course, 1);
+        assert_eq!(reputation.total_points, u64::MAX);
+        assert_eq!(reputation.courses[0].points, u64::MAX);
+    }
+}
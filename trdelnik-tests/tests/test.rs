@@ -1,17 +1,36 @@
-use anchor_lang::solana_program::{blake3, sysvar::rent};
-use anchor_lang::system_program;
+use anchor_lang::solana_program::{
+    blake3, ed25519_program,
+    instruction::{AccountMeta, Instruction},
+    sysvar::{instructions, rent},
+};
+use anchor_lang::{system_program, AccountDeserialize, InstructionData, ToAccountMetas};
 use anchor_spl::associated_token::{self, get_associated_token_address};
 use anchor_spl::token;
 use fehler::throws;
+use mpl_token_metadata::state::{Metadata, TokenMetadataAccount};
 use program_client::course_batch_manager_instruction;
 use program_client::course_manager_instruction;
+use program_client::reputation_instruction;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use trdelnik_client::{anyhow::Result, *};
 
 #[throws]
 #[fixture]
 async fn start_course_batch() -> Fixture {
+    setup_course_batch(true).await?
+}
+
+/// Course batch deployed before the upgrade authority creates the reputation registry
+#[throws]
+#[fixture]
+async fn start_course_batch_without_registry() -> Fixture {
+    setup_course_batch(false).await?
+}
+
+#[throws]
+async fn setup_course_batch(with_registry: bool) -> Fixture {
     let mut f = Fixture::new();
-    // Deploy course manager, course batch manager and assignment checker programs
+    // Deploy course manager, course batch manager, assignment checker and reputation programs
     f.deploy().await?;
 
     // Airdrop some lamports to the course authority and students A and B
@@ -29,6 +48,7 @@ async fn start_course_batch() -> Fixture {
         f.course_id,
         f.course_authority.pubkey(),
         f.course_pda,
+        course_manager::course_metadata_canonical_pda(f.course_pda),
         system_program::ID,
         [f.course_authority.clone()],
     )
@@ -44,8 +64,20 @@ async fn start_course_batch() -> Fixture {
     course_batch_manager_instruction::create_new_batch(
         &f.client,
         f.batch_id,
+        // the batch is open forever
+        course_batch_manager::BatchSchedule {
+            enrollment_opens_at: 0,
+            enrollment_closes_at: i64::MAX,
+            starts_at: 0,
+            ends_at: i64::MAX,
+        },
+        // any number of students can enroll
+        course_batch_manager::EnrollmentPolicy::default(),
+        // batch tokens are transferable
+        false,
         f.course_authority.pubkey(),
         f.course_pda,
+        course_manager::course_metadata_canonical_pda(f.course_pda),
         f.course_batch_pda,
         f.course_batch_mint_pda,
         system_program::ID,
@@ -55,41 +87,32 @@ async fn start_course_batch() -> Fixture {
     )
     .await?;
 
+    if with_registry {
+        // only the upgrade authority of the reputation program, the deployer, creates the registry
+        assert!(f.init_registry(f.course_authority.clone()).await.is_err());
+        f.init_registry(system_keypair(0)).await?;
+        // course batch manager is allowed to update reputations of students A and B
+        reputation_instruction::add_writer(
+            &f.client,
+            course_batch_manager::ID,
+            system_keypair(0).pubkey(),
+            reputation::config_canonical_pda(),
+            [system_keypair(0)],
+        )
+        .await?;
+    }
+    f.init_reputation(f.student_a.pubkey()).await?;
+    f.init_reputation(f.student_b.pubkey()).await?;
+
     // enroll student_a into the batch and create course batch associated token account
     f.student_a_token_account =
         get_associated_token_address(&f.student_a.pubkey(), &f.course_batch_mint_pda);
-    course_batch_manager_instruction::enroll_batch(
-        &f.client,
-        f.student_a.pubkey(),
-        f.course_authority.pubkey(),
-        f.course_batch_pda,
-        f.course_batch_mint_pda,
-        f.student_a_token_account,
-        system_program::ID,
-        token::ID,
-        associated_token::ID,
-        rent::id(),
-        [f.student_a.clone()],
-    )
-    .await?;
+    f.enroll_batch(f.student_a.clone(), vec![]).await?;
 
     // enroll student_b into the batch and create course batch associated token account
     f.student_b_token_account =
         get_associated_token_address(&f.student_b.pubkey(), &f.course_batch_mint_pda);
-    course_batch_manager_instruction::enroll_batch(
-        &f.client,
-        f.student_b.pubkey(),
-        f.course_authority.pubkey(),
-        f.course_batch_pda,
-        f.course_batch_mint_pda,
-        f.student_b_token_account,
-        system_program::ID,
-        token::ID,
-        associated_token::ID,
-        rent::id(),
-        [f.student_b.clone()],
-    )
-    .await?;
+    f.enroll_batch(f.student_b.clone(), vec![]).await?;
 
     // Prepare assignment checker capable to check 10 - 1 students
 
@@ -106,10 +129,18 @@ async fn start_course_batch() -> Fixture {
     course_batch_manager_instruction::create_assignment_checker(
         &f.client,
         f.assignment_id,
-        f.hash_chain_length,
-        100,
-        f.salt.clone(),
-        ground_truth_hash_chain_tail,
+        None,
+        course_batch_manager::CheckerConfig {
+            hash_chain_length: f.hash_chain_length,
+            to_mint_on_successful_check: 100,
+            salt: f.salt.clone(),
+            ground_truth_hash_chain_tail,
+            reveal_delay_slots: None,
+            kind: course_batch_manager::CheckerKind::HashChain,
+            reward_schedule: None,
+            parts: vec![],
+            attempt_policy: course_batch_manager::AttemptPolicy::default(),
+        },
         f.course_authority.pubkey(),
         f.course_pda,
         f.assignment_checker_pda,
@@ -121,38 +152,10 @@ async fn start_course_batch() -> Fixture {
     .await?;
 
     // init check result accounts for students A and B
-    course_batch_manager_instruction::create_check_result(
-        &f.client,
-        f.assignment_id,
-        f.student_a.pubkey(),
-        f.course_pda,
-        course_batch_manager::check_result_canonical_pda(
-            f.student_a.pubkey(),
-            f.course_pda,
-            &f.assignment_id,
-        ),
-        assignment_checker::ID,
-        course_batch_manager::ID,
-        system_program::ID,
-        [f.student_a.clone()],
-    )
-    .await?;
-    course_batch_manager_instruction::create_check_result(
-        &f.client,
-        f.assignment_id,
-        f.student_b.pubkey(),
-        f.course_pda,
-        course_batch_manager::check_result_canonical_pda(
-            f.student_b.pubkey(),
-            f.course_pda,
-            &f.assignment_id,
-        ),
-        assignment_checker::ID,
-        course_batch_manager::ID,
-        system_program::ID,
-        [f.student_b.clone()],
-    )
-    .await?;
+    f.create_check_result(f.student_a.clone(), f.assignment_id, None)
+        .await?;
+    f.create_check_result(f.student_b.clone(), f.assignment_id, None)
+        .await?;
 
     f
 }
@@ -190,11 +193,24 @@ async fn test_check_assignment(#[future] start_course_batch: Result<Fixture>) {
 
     assert_eq!(check_result.check_passed, true);
     assert_eq!(check_result.passed_first_time, true);
+    assert_eq!(check_result.attempts, 1);
+    assert!(check_result.passed_at.is_some());
     let balance_a = f
         .client
         .get_token_balance(f.student_a_token_account)
         .await?;
     assert_eq!(balance_a.amount.as_str(), "100");
+    let enrollment_a = f.get_enrollment_account(f.student_a.pubkey()).await?;
+    assert_eq!(
+        enrollment_a.status,
+        course_batch_manager::EnrollmentStatus::Active
+    );
+    assert_eq!(enrollment_a.assignments_passed, 1);
+    assert_eq!(enrollment_a.tokens_earned, 100);
+    let reputation_a = f.get_reputation_account(f.student_a.pubkey()).await?;
+    assert_eq!(reputation_a.total_points, 100);
+    assert_eq!(reputation_a.courses.len(), 1);
+    assert_eq!(reputation_a.courses[0].course, f.course_pda);
 
     // student_b tries to send the same hash value as student_a and fails the check
     // the chain became shorter and needs new hash
@@ -243,109 +259,2917 @@ async fn test_check_assignment(#[future] start_course_batch: Result<Fixture>) {
     assert_eq!(balance_a.amount.as_str(), "100");
 }
 
-/// Input keypairs / pubkeys / programs and data to configure tests
-struct Fixture {
-    client: Client,
-    assignment_checker_program: Keypair,
-    course_program: Keypair,
-    course_batch_program: Keypair,
+/// Test if tokens are minted to the student who hasn't created the reputation account
+#[trdelnik_test]
+async fn test_check_without_reputation(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let student_c = keypair(5);
+    f.client.airdrop(student_c.pubkey(), 5_000_000).await?;
+    f.enroll_batch(student_c.clone(), vec![]).await?;
+    f.create_check_result(student_c.clone(), f.assignment_id, None)
+        .await?;
 
-    course_authority: Keypair,
-    course_id: [u8; 16],
-    course_pda: Pubkey,
+    let student_c_token_account =
+        get_associated_token_address(&student_c.pubkey(), &f.course_batch_mint_pda);
+    let (_, check_result) = f
+        .check_assignment(
+            student_c.clone(),
+            student_c_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+    let balance_c = f.client.get_token_balance(student_c_token_account).await?;
+    assert_eq!(balance_c.amount.as_str(), "100");
+    assert!(f.get_reputation_account(student_c.pubkey()).await.is_err());
+}
 
-    batch_id: [u8; 16],
-    course_batch_pda: Pubkey,
-    course_batch_mint_pda: Pubkey,
+/// Test if tokens are minted before the reputation registry is created
+/// and while the course batch manager is not a reputation writer
+#[trdelnik_test]
+async fn test_check_without_registry(
+    #[future] start_course_batch_without_registry: Result<Fixture>,
+) {
+    let f = start_course_batch_without_registry.await?;
 
-    assignment_id: [u8; 16],
-    ground_truth_value: String,
-    hash_chain_length: u16,
-    salt: [u8; 32],
-    assignment_checker_pda: Pubkey,
+    // no registry, the reputation of student_a is not updated
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+    let balance_a = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance_a.amount.as_str(), "100");
+    let reputation_a = f.get_reputation_account(f.student_a.pubkey()).await?;
+    assert_eq!(reputation_a.total_points, 0);
 
-    // can be related to course_authority
-    student_a: Keypair,
-    student_a_token_account: Pubkey,
-    student_b: Keypair,
-    student_b_token_account: Pubkey,
+    // the registry doesn't list the course batch manager, the reputation of student_b is not updated
+    f.init_registry(system_keypair(0)).await?;
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_b.clone(),
+            f.student_b_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+    let balance_b = f
+        .client
+        .get_token_balance(f.student_b_token_account)
+        .await?;
+    assert_eq!(balance_b.amount.as_str(), "100");
+    let reputation_b = f.get_reputation_account(f.student_b.pubkey()).await?;
+    assert_eq!(reputation_b.total_points, 0);
 }
 
-impl Fixture {
-    fn new() -> Self {
-        Fixture {
-            client: Client::new(system_keypair(0)),
-            assignment_checker_program: program_keypair(1),
-            course_program: program_keypair(2),
-            course_batch_program: program_keypair(3),
-            course_authority: keypair(0),
-            course_id: *b"web2_to_web3____",
-            course_pda: Pubkey::default(),
-            batch_id: *b"the_first_batch_",
-            course_batch_pda: Pubkey::default(),
-            course_batch_mint_pda: Pubkey::default(),
-            assignment_id: *b"space_hero______",
-            // Assignment: "Surname of the first man in space"
-            ground_truth_value: "Gagarin".to_string(),
-            // support up to 10 - 1 students
-            hash_chain_length: 10,
-            // good enough for test
-            salt: [0; 32],
-            assignment_checker_pda: Pubkey::default(),
+/// Test if committed solution hash is revealed only by its student and tokens are minted on reveal
+#[trdelnik_test]
+async fn test_commit_reveal(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
 
-            student_a: keypair(1),
-            student_a_token_account: Pubkey::default(),
-            student_b: keypair(2),
-            student_b_token_account: Pubkey::default(),
-        }
-    }
+    let assignment_id = *b"commit_reveal___";
+    let checker_pda =
+        course_batch_manager::assignment_checker_canonical_pda(f.course_pda, &assignment_id);
+    course_batch_manager_instruction::create_assignment_checker(
+        &f.client,
+        assignment_id,
+        None,
+        course_batch_manager::CheckerConfig {
+            hash_chain_length: f.hash_chain_length,
+            to_mint_on_successful_check: 100,
+            salt: f.salt.clone(),
+            ground_truth_hash_chain_tail: Fixture::hash(
+                f.hash_chain_length,
+                &f.salt,
+                f.ground_truth_value.as_bytes(),
+            ),
+            reveal_delay_slots: Some(2),
+            kind: course_batch_manager::CheckerKind::HashChain,
+            reward_schedule: None,
+            parts: vec![],
+            attempt_policy: course_batch_manager::AttemptPolicy::default(),
+        },
+        f.course_authority.pubkey(),
+        f.course_pda,
+        checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        system_program::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    f.create_check_result(f.student_a.clone(), assignment_id, None)
+        .await?;
+    f.create_check_result(f.student_b.clone(), assignment_id, None)
+        .await?;
 
-    #[throws]
-    async fn deploy(&mut self) {
-        self.client
-            .deploy_by_name(&self.course_program, "course_manager")
-            .await?;
-        self.client
-            .deploy_by_name(&self.course_batch_program, "course_batch_manager")
-            .await?;
-        self.client
-            .deploy_by_name(&self.assignment_checker_program, "assignment_checker")
-            .await?;
-    }
+    let hash_chain_tail_parent = Fixture::hash(
+        f.hash_chain_length - 1,
+        &f.salt,
+        f.ground_truth_value.as_bytes(),
+    );
+    let nonce = [3; 32];
 
-    #[throws]
-    async fn get_course_account(&self) -> course_manager::Course {
-        self.client
-            .account_data::<course_manager::Course>(self.course_pda)
-            .await?
-    }
+    // the solution hash can't be sent in clear
+    assert!(f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await
+        .is_err());
+    // nothing to reveal without a commit
+    assert!(f
+        .reveal_assignment(
+            f.student_a.clone(),
+            checker_pda,
+            hash_chain_tail_parent,
+            nonce
+        )
+        .await
+        .is_err());
 
-    #[throws]
-    async fn get_course_batch_account(&self) -> course_batch_manager::CourseBatch {
-        self.client
-            .account_data::<course_batch_manager::CourseBatch>(self.course_batch_pda)
-            .await?
-    }
+    // student_b copies the commitment of student_a
+    let commitment =
+        assignment_checker::commitment_hash(&f.student_a.pubkey(), &hash_chain_tail_parent, &nonce);
+    f.commit_assignment(f.student_a.clone(), checker_pda, commitment)
+        .await?;
+    f.commit_assignment(f.student_b.clone(), checker_pda, commitment)
+        .await?;
+    // nothing is minted on commit
+    let balance_a = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance_a.amount.as_str(), "0");
 
-    #[throws]
-    async fn get_checker_account(
-        &self,
-        checker_pda: Pubkey,
-    ) -> assignment_checker::AssignmentCheckerState {
-        self.client
-            .account_data::<course_batch_manager::AssignmentCheckerState>(checker_pda)
-            .await?
-    }
+    std::thread::sleep(Duration::from_secs(2));
+    // the commitment binds student_a, so student_b can't reveal it
+    assert!(f
+        .reveal_assignment(
+            f.student_b.clone(),
+            checker_pda,
+            hash_chain_tail_parent,
+            nonce
+        )
+        .await
+        .is_err());
+    // the revealed values should match the commitment
+    assert!(f
+        .reveal_assignment(
+            f.student_a.clone(),
+            checker_pda,
+            hash_chain_tail_parent,
+            [4; 32]
+        )
+        .await
+        .is_err());
 
-    #[throws]
-    async fn get_check_result_account(
-        &self,
-        check_result_pda: Pubkey,
-    ) -> assignment_checker::CheckResult {
-        self.client
-            .account_data::<course_batch_manager::CheckResult>(check_result_pda)
-            .await?
-    }
+    let check_result = f
+        .reveal_assignment(
+            f.student_a.clone(),
+            checker_pda,
+            hash_chain_tail_parent,
+            nonce,
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+    assert_eq!(check_result.passed_first_time, true);
+    assert_eq!(check_result.commitment, None);
+    let balance_a = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance_a.amount.as_str(), "100");
+    let balance_b = f
+        .client
+        .get_token_balance(f.student_b_token_account)
+        .await?;
+    assert_eq!(balance_b.amount.as_str(), "0");
+}
+
+/// Test if exhausted hash chain can be refilled by the course authority
+#[trdelnik_test]
+async fn test_refill_hash_chain(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    // the shortest chain allows a single successful check
+    let salt = [1; 32];
+    course_batch_manager_instruction::refill_hash_chain(
+        &f.client,
+        2,
+        salt,
+        Fixture::hash(2, &salt, f.ground_truth_value.as_bytes()),
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let checker_account = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker_account.hash_chain_generation, 1);
+    assert_eq!(checker_account.salt, salt);
+
+    // co-instructor can't refill the chain
+    let co_instructor = keypair(3);
+    f.add_co_instructor(co_instructor.pubkey()).await?;
+    assert!(course_batch_manager_instruction::refill_hash_chain(
+        &f.client,
+        2,
+        salt,
+        Fixture::hash(2, &salt, f.ground_truth_value.as_bytes()),
+        co_instructor.pubkey(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [co_instructor.clone()],
+    )
+    .await
+    .is_err());
+
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+
+    // the chain is used up
+    let checker_account = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker_account.hash_chain_length, 1);
+
+    // the course authority installs the next generation of the chain
+    let salt = [2; 32];
+    course_batch_manager_instruction::refill_hash_chain(
+        &f.client,
+        f.hash_chain_length,
+        salt,
+        Fixture::hash(f.hash_chain_length, &salt, f.ground_truth_value.as_bytes()),
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let checker_account = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker_account.hash_chain_generation, 2);
+
+    // student_b passes against the new chain, result of student_a is kept
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_b.clone(),
+            f.student_b_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+    assert_eq!(check_result.passed_first_time, true);
+    let check_result = f
+        .get_check_result_account(course_batch_manager::check_result_canonical_pda(
+            f.student_a.pubkey(),
+            f.course_pda,
+            &f.assignment_id,
+        ))
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+}
+
+/// Test if student-bound answer hash of one student is rejected for another student
+#[trdelnik_test]
+async fn test_student_bound_check(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    let assignment_id = *b"moon_hero_______";
+    // Assignment: "Surname of the first man on the Moon"
+    let value = b"Armstrong";
+    let answer_hash_a =
+        assignment_checker::student_bound_answer_hash(&f.salt, &f.student_a.pubkey(), value);
+    let answer_hash_b =
+        assignment_checker::student_bound_answer_hash(&f.salt, &f.student_b.pubkey(), value);
+    let leaf_a = assignment_checker::student_bound_leaf(&f.student_a.pubkey(), &answer_hash_a);
+    let leaf_b = assignment_checker::student_bound_leaf(&f.student_b.pubkey(), &answer_hash_b);
+    let answers_root = assignment_checker::merkle_node_hash(&leaf_a, &leaf_b);
+
+    let checker_pda =
+        course_batch_manager::assignment_checker_canonical_pda(f.course_pda, &assignment_id);
+    course_batch_manager_instruction::create_assignment_checker(
+        &f.client,
+        assignment_id,
+        None,
+        course_batch_manager::CheckerConfig {
+            hash_chain_length: 0,
+            to_mint_on_successful_check: 100,
+            salt: f.salt.clone(),
+            ground_truth_hash_chain_tail: [0; 32],
+            reveal_delay_slots: None,
+            kind: course_batch_manager::CheckerKind::StudentBound { answers_root },
+            reward_schedule: None,
+            parts: vec![],
+            attempt_policy: course_batch_manager::AttemptPolicy::default(),
+        },
+        f.course_authority.pubkey(),
+        f.course_pda,
+        checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        system_program::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    f.create_check_result(f.student_a.clone(), assignment_id, None)
+        .await?;
+    f.create_check_result(f.student_b.clone(), assignment_id, None)
+        .await?;
+
+    // student_b replays the answer hash of student_a and fails the check
+    let check_result = f
+        .check_student_bound(
+            f.student_b.clone(),
+            checker_pda,
+            answer_hash_a,
+            vec![leaf_a],
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, false);
+
+    // student_a passes with own answer hash
+    let check_result = f
+        .check_student_bound(
+            f.student_a.clone(),
+            checker_pda,
+            answer_hash_a,
+            vec![leaf_b],
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+    assert_eq!(check_result.passed_first_time, true);
+    let balance_a = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance_a.amount.as_str(), "100");
+
+    // student_b passes with own answer hash, the checker is not consumed
+    let check_result = f
+        .check_student_bound(
+            f.student_b.clone(),
+            checker_pda,
+            answer_hash_b,
+            vec![leaf_a],
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+    assert_eq!(check_result.passed_first_time, true);
+}
+
+/// Test if tokens are minted in proportion to the score signed by the grader
+#[trdelnik_test]
+async fn test_grader_attestation(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let grader = keypair(6);
+
+    let assignment_id = *b"graded_essay____";
+    let checker_pda =
+        course_batch_manager::assignment_checker_canonical_pda(f.course_pda, &assignment_id);
+    course_batch_manager_instruction::create_assignment_checker(
+        &f.client,
+        assignment_id,
+        None,
+        course_batch_manager::CheckerConfig {
+            hash_chain_length: 0,
+            to_mint_on_successful_check: 100,
+            salt: f.salt.clone(),
+            ground_truth_hash_chain_tail: [0; 32],
+            reveal_delay_slots: None,
+            kind: course_batch_manager::CheckerKind::GraderAttestation {
+                grader: grader.pubkey(),
+                min_score: 50,
+                max_score: 80,
+            },
+            reward_schedule: None,
+            parts: vec![],
+            attempt_policy: course_batch_manager::AttemptPolicy::default(),
+        },
+        f.course_authority.pubkey(),
+        f.course_pda,
+        checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        system_program::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    f.create_check_result(f.student_a.clone(), assignment_id, None)
+        .await?;
+
+    // student_a signs the score on their own
+    let student_a = f.student_a.clone();
+    assert!(f
+        .check_grader_attestation(f.student_a.clone(), checker_pda, &student_a, 80)
+        .await
+        .is_err());
+    // the score is above the max score of the grader
+    assert!(f
+        .check_grader_attestation(f.student_a.clone(), checker_pda, &grader, 81)
+        .await
+        .is_err());
+
+    // the score is below the min score, the check fails and nothing is minted
+    let check_result = f
+        .check_grader_attestation(f.student_a.clone(), checker_pda, &grader, 40)
+        .await?;
+    assert_eq!(check_result.check_passed, false);
+    assert_eq!(check_result.score, 40);
+    let balance_a = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance_a.amount.as_str(), "0");
+
+    // 60 out of 80 gets 75 out of 100 tokens
+    let check_result = f
+        .check_grader_attestation(f.student_a.clone(), checker_pda, &grader, 60)
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+    assert_eq!(check_result.passed_first_time, true);
+    assert_eq!(check_result.score, 60);
+    let balance_a = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance_a.amount.as_str(), "75");
+    let enrollment_a = f.get_enrollment_account(f.student_a.pubkey()).await?;
+    assert_eq!(enrollment_a.tokens_earned, 75);
+
+    // a better score later doesn't mint again
+    let check_result = f
+        .check_grader_attestation(f.student_a.clone(), checker_pda, &grader, 80)
+        .await?;
+    assert_eq!(check_result.passed_first_time, false);
+    assert_eq!(check_result.score, 80);
+    let balance_a = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance_a.amount.as_str(), "75");
+}
+
+/// Test if proofs of the replaced answers root are rejected and proofs of the new root pass
+#[trdelnik_test]
+async fn test_update_answers_root(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    let assignment_id = *b"moon_hero_______";
+    let answers_tree = |value: &[u8]| {
+        let answer_hash_a =
+            assignment_checker::student_bound_answer_hash(&f.salt, &f.student_a.pubkey(), value);
+        let answer_hash_b =
+            assignment_checker::student_bound_answer_hash(&f.salt, &f.student_b.pubkey(), value);
+        let leaf_a = assignment_checker::student_bound_leaf(&f.student_a.pubkey(), &answer_hash_a);
+        let leaf_b = assignment_checker::student_bound_leaf(&f.student_b.pubkey(), &answer_hash_b);
+        // (root, answer hash of student_a, proof of student_a)
+        (
+            assignment_checker::merkle_node_hash(&leaf_a, &leaf_b),
+            answer_hash_a,
+            vec![leaf_b],
+        )
+    };
+    let (old_root, old_answer_hash, old_proof) = answers_tree(b"Armstrong");
+    let (new_root, new_answer_hash, new_proof) = answers_tree(b"Aldrin");
+
+    let checker_pda =
+        course_batch_manager::assignment_checker_canonical_pda(f.course_pda, &assignment_id);
+    course_batch_manager_instruction::create_assignment_checker(
+        &f.client,
+        assignment_id,
+        None,
+        course_batch_manager::CheckerConfig {
+            hash_chain_length: 0,
+            to_mint_on_successful_check: 100,
+            salt: f.salt.clone(),
+            ground_truth_hash_chain_tail: [0; 32],
+            reveal_delay_slots: None,
+            kind: course_batch_manager::CheckerKind::StudentBound {
+                answers_root: old_root,
+            },
+            reward_schedule: None,
+            parts: vec![],
+            attempt_policy: course_batch_manager::AttemptPolicy::default(),
+        },
+        f.course_authority.pubkey(),
+        f.course_pda,
+        checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        system_program::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    f.create_check_result(f.student_a.clone(), assignment_id, None)
+        .await?;
+
+    // student can't replace the root
+    assert!(course_batch_manager_instruction::update_answers_root(
+        &f.client,
+        new_root,
+        f.student_a.pubkey(),
+        f.course_pda,
+        checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.student_a.clone()],
+    )
+    .await
+    .is_err());
+
+    course_batch_manager_instruction::update_answers_root(
+        &f.client,
+        new_root,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let checker_account = f.get_checker_account(checker_pda).await?;
+    assert!(
+        checker_account.kind
+            == course_batch_manager::CheckerKind::StudentBound {
+                answers_root: new_root
+            }
+    );
+
+    // the proof of the old root is rejected
+    let check_result = f
+        .check_student_bound(f.student_a.clone(), checker_pda, old_answer_hash, old_proof)
+        .await?;
+    assert_eq!(check_result.check_passed, false);
+
+    // the proof of the new root passes
+    let check_result = f
+        .check_student_bound(f.student_a.clone(), checker_pda, new_answer_hash, new_proof)
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+    assert_eq!(check_result.passed_first_time, true);
+    let balance_a = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance_a.amount.as_str(), "100");
+}
+
+/// Test if enrollment is limited by the seat count and the allow-list of the batch
+#[trdelnik_test]
+async fn test_enrollment_policy(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let student_c = keypair(3);
+    f.client.airdrop(student_c.pubkey(), 5_000_000).await?;
+
+    // student_a and student_b have taken both seats
+    course_batch_manager_instruction::update_enrollment_policy(
+        &f.client,
+        course_batch_manager::EnrollmentPolicy {
+            max_seats: Some(2),
+            allow_list: course_batch_manager::AllowList::Open,
+        },
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    assert!(f.enroll_batch(student_c.clone(), vec![]).await.is_err());
+
+    // the only leaf of the allow-list is the root itself
+    let root = course_batch_manager::allow_list_leaf(&f.course_batch_pda, &student_c.pubkey());
+    course_batch_manager_instruction::update_enrollment_policy(
+        &f.client,
+        course_batch_manager::EnrollmentPolicy {
+            max_seats: Some(3),
+            allow_list: course_batch_manager::AllowList::MerkleRoot { root },
+        },
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    // student_d is not on the allow-list
+    let student_d = keypair(4);
+    f.client.airdrop(student_d.pubkey(), 5_000_000).await?;
+    assert!(f.enroll_batch(student_d.clone(), vec![]).await.is_err());
+    // and can't start solving assignments without the enrollment record
+    assert!(f
+        .create_check_result(student_d, f.assignment_id, None)
+        .await
+        .is_err());
+
+    f.enroll_batch(student_c, vec![]).await?;
+    let course_batch_account = f.get_course_batch_account().await?;
+    assert_eq!(course_batch_account.enrolled_count, 3);
+}
+
+/// Test if expelled student can't check assignments until reinstated
+#[trdelnik_test]
+async fn test_expel_and_reinstate(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let enrollment_b =
+        course_batch_manager::enrollment_canonical_pda(f.student_b.pubkey(), f.course_batch_pda);
+
+    course_batch_manager_instruction::expel_student(
+        &f.client,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        enrollment_b,
+        f.course_batch_mint_pda,
+        f.student_b_token_account,
+        token::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let enrollment_account = f.get_enrollment_account(f.student_b.pubkey()).await?;
+    assert_eq!(
+        enrollment_account.status,
+        course_batch_manager::EnrollmentStatus::Expelled
+    );
+    let course_batch_account = f.get_course_batch_account().await?;
+    assert_eq!(course_batch_account.enrolled_count, 1);
+
+    // expelled student_b can't check the assignment
+    assert!(f
+        .check_assignment(
+            f.student_b.clone(),
+            f.student_b_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await
+        .is_err());
+
+    course_batch_manager_instruction::reinstate_student(
+        &f.client,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        enrollment_b,
+        f.course_batch_mint_pda,
+        f.student_b_token_account,
+        token::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+
+    // the token account is thawed and gets the reward
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_b.clone(),
+            f.student_b_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+    let balance_b = f
+        .client
+        .get_token_balance(f.student_b_token_account)
+        .await?;
+    assert_eq!(balance_b.amount.as_str(), "100");
+
+    // student_a leaves the batch
+    course_batch_manager_instruction::withdraw_from_batch(
+        &f.client,
+        f.student_a.pubkey(),
+        f.course_batch_pda,
+        course_batch_manager::enrollment_canonical_pda(f.student_a.pubkey(), f.course_batch_pda),
+        f.course_batch_mint_pda,
+        f.student_a_token_account,
+        token::ID,
+        [f.student_a.clone()],
+    )
+    .await?;
+    let enrollment_account = f.get_enrollment_account(f.student_a.pubkey()).await?;
+    assert_eq!(
+        enrollment_account.status,
+        course_batch_manager::EnrollmentStatus::Withdrawn
+    );
+}
+
+/// Test if course metadata versions increase and new batches record the current version
+#[trdelnik_test]
+async fn test_course_metadata(#[future] start_course_batch: Result<Fixture>) {
+    let mut f = start_course_batch.await?;
+    let course_metadata = course_manager::course_metadata_canonical_pda(f.course_pda);
+    let course_batch_account = f.get_course_batch_account().await?;
+    assert_eq!(course_batch_account.syllabus_version, 0);
+
+    course_manager_instruction::update_course_metadata(
+        &f.client,
+        "From Web2 to Web3".to_string(),
+        "https://example.com/syllabus_v1.json".to_string(),
+        [1; 32],
+        1,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        course_metadata,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let metadata_account = f
+        .client
+        .account_data::<course_manager::CourseMetadata>(course_metadata)
+        .await?;
+    assert_eq!(metadata_account.course, f.course_pda);
+    assert_eq!(metadata_account.title, "From Web2 to Web3");
+    assert_eq!(
+        metadata_account.content_uri,
+        "https://example.com/syllabus_v1.json"
+    );
+    assert_eq!(metadata_account.content_hash, [1; 32]);
+    assert_eq!(metadata_account.version, 1);
+
+    // the version should increase
+    assert!(course_manager_instruction::update_course_metadata(
+        &f.client,
+        "From Web2 to Web3".to_string(),
+        "https://example.com/syllabus_v1.json".to_string(),
+        [2; 32],
+        1,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        course_metadata,
+        [f.course_authority.clone()],
+    )
+    .await
+    .is_err());
+    // the title and the URI are limited
+    assert!(course_manager_instruction::update_course_metadata(
+        &f.client,
+        "t".repeat(course_manager::MAX_TITLE_LEN + 1),
+        "https://example.com/syllabus_v2.json".to_string(),
+        [2; 32],
+        2,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        course_metadata,
+        [f.course_authority.clone()],
+    )
+    .await
+    .is_err());
+    assert!(course_manager_instruction::update_course_metadata(
+        &f.client,
+        "From Web2 to Web3".to_string(),
+        "u".repeat(course_manager::MAX_CONTENT_URI_LEN + 1),
+        [2; 32],
+        2,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        course_metadata,
+        [f.course_authority.clone()],
+    )
+    .await
+    .is_err());
+    // only the course authority publishes the syllabus
+    assert!(course_manager_instruction::update_course_metadata(
+        &f.client,
+        "From Web2 to Web3".to_string(),
+        "https://example.com/syllabus_v2.json".to_string(),
+        [2; 32],
+        2,
+        f.student_a.pubkey(),
+        f.course_pda,
+        course_metadata,
+        [f.student_a.clone()],
+    )
+    .await
+    .is_err());
+
+    // the running batch keeps its version, the next batch runs the new one
+    let course_batch_account = f.get_course_batch_account().await?;
+    assert_eq!(course_batch_account.syllabus_version, 0);
+    f.create_batch(
+        *b"the_second_batch",
+        course_batch_manager::BatchSchedule {
+            enrollment_opens_at: 0,
+            enrollment_closes_at: i64::MAX,
+            starts_at: 0,
+            ends_at: i64::MAX,
+        },
+    )
+    .await?;
+    let course_batch_account = f.get_course_batch_account().await?;
+    assert_eq!(course_batch_account.syllabus_version, 1);
+}
+
+/// Test if students enroll and check assignments only within the batch schedule
+#[trdelnik_test]
+async fn test_batch_schedule(#[future] start_course_batch: Result<Fixture>) {
+    let mut f = start_course_batch.await?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+
+    // the enrollment hasn't opened yet
+    f.create_batch(
+        *b"future_batch____",
+        course_batch_manager::BatchSchedule {
+            enrollment_opens_at: now + 3600,
+            enrollment_closes_at: i64::MAX,
+            starts_at: now + 3600,
+            ends_at: i64::MAX,
+        },
+    )
+    .await?;
+    assert!(f.enroll_batch(f.student_a.clone(), vec![]).await.is_err());
+
+    // the enrollment has closed, the batch is running
+    f.create_batch(
+        *b"closed_batch____",
+        course_batch_manager::BatchSchedule {
+            enrollment_opens_at: 0,
+            enrollment_closes_at: now - 3600,
+            starts_at: 0,
+            ends_at: i64::MAX,
+        },
+    )
+    .await?;
+    assert!(f.enroll_batch(f.student_a.clone(), vec![]).await.is_err());
+
+    // the enrollment window excludes its end, so the empty window is never open
+    f.create_batch(
+        *b"empty_window____",
+        course_batch_manager::BatchSchedule {
+            enrollment_opens_at: now - 3600,
+            enrollment_closes_at: now - 3600,
+            starts_at: 0,
+            ends_at: i64::MAX,
+        },
+    )
+    .await?;
+    assert!(f.enroll_batch(f.student_a.clone(), vec![]).await.is_err());
+
+    // student_a enrolls but can't check assignments before the batch starts
+    f.create_batch(
+        *b"not_started_____",
+        course_batch_manager::BatchSchedule {
+            enrollment_opens_at: 0,
+            enrollment_closes_at: i64::MAX,
+            starts_at: now + 3600,
+            ends_at: i64::MAX,
+        },
+    )
+    .await?;
+    f.enroll_batch(f.student_a.clone(), vec![]).await?;
+    let token_a = get_associated_token_address(&f.student_a.pubkey(), &f.course_batch_mint_pda);
+    assert!(f
+        .check_assignment(
+            f.student_a.clone(),
+            token_a,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await
+        .is_err());
+
+    // and after it ends
+    f.create_batch(
+        *b"ended_batch_____",
+        course_batch_manager::BatchSchedule {
+            enrollment_opens_at: 0,
+            enrollment_closes_at: i64::MAX,
+            starts_at: 0,
+            ends_at: now - 3600,
+        },
+    )
+    .await?;
+    f.enroll_batch(f.student_a.clone(), vec![]).await?;
+    let token_a = get_associated_token_address(&f.student_a.pubkey(), &f.course_batch_mint_pda);
+    assert!(f
+        .check_assignment(
+            f.student_a.clone(),
+            token_a,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await
+        .is_err());
+    let balance_a = f.client.get_token_balance(token_a).await?;
+    assert_eq!(balance_a.amount.as_str(), "0");
+
+    // the end of the batch must not precede its start
+    assert!(f
+        .create_batch(
+            *b"invalid_batch___",
+            course_batch_manager::BatchSchedule {
+                enrollment_opens_at: 0,
+                enrollment_closes_at: i64::MAX,
+                starts_at: now,
+                ends_at: now - 1,
+            },
+        )
+        .await
+        .is_err());
+}
+
+/// Test if assignments are checked in a batch with soulbound tokens
+#[trdelnik_test]
+async fn test_soulbound_batch(#[future] start_course_batch: Result<Fixture>) {
+    let mut f = start_course_batch.await?;
+
+    f.batch_id = *b"soulbound_batch_";
+    f.course_batch_pda = course_batch_manager::batch_canonical_pda(f.course_pda, &f.batch_id);
+    f.course_batch_mint_pda =
+        course_batch_manager::batch_mint_canonical_pda(f.course_pda, &f.batch_id);
+    course_batch_manager_instruction::create_new_batch(
+        &f.client,
+        f.batch_id,
+        course_batch_manager::BatchSchedule {
+            enrollment_opens_at: 0,
+            enrollment_closes_at: i64::MAX,
+            starts_at: 0,
+            ends_at: i64::MAX,
+        },
+        course_batch_manager::EnrollmentPolicy::default(),
+        true,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        course_manager::course_metadata_canonical_pda(f.course_pda),
+        f.course_batch_pda,
+        f.course_batch_mint_pda,
+        system_program::ID,
+        rent::id(),
+        token::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    f.enroll_batch(f.student_a.clone(), vec![]).await?;
+    f.student_a_token_account =
+        get_associated_token_address(&f.student_a.pubkey(), &f.course_batch_mint_pda);
+
+    // the frozen token account is thawed to mint the reward
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+    let balance_a = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance_a.amount.as_str(), "100");
+}
+
+/// Test if multi-part checker mints the points of each part passed the first time
+#[trdelnik_test]
+async fn test_multi_part_checker(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    let assignment_id = *b"space_quiz______";
+    // Surnames of the first man in space, the first woman in space and the first man on the Moon
+    let answers = ["Gagarin", "Tereshkova", "Armstrong"];
+    let points = [10, 20, 30];
+    let parts = answers
+        .iter()
+        .zip(points)
+        .map(|(answer, points)| course_batch_manager::AssignmentPart {
+            hash_chain_length: f.hash_chain_length,
+            ground_truth_hash_chain_tail: Fixture::hash(
+                f.hash_chain_length,
+                &f.salt,
+                answer.as_bytes(),
+            ),
+            points,
+        })
+        .collect();
+    let checker_pda =
+        course_batch_manager::assignment_checker_canonical_pda(f.course_pda, &assignment_id);
+    course_batch_manager_instruction::create_assignment_checker(
+        &f.client,
+        assignment_id,
+        None,
+        course_batch_manager::CheckerConfig {
+            hash_chain_length: 0,
+            to_mint_on_successful_check: 0,
+            salt: f.salt.clone(),
+            ground_truth_hash_chain_tail: [0; 32],
+            reveal_delay_slots: None,
+            kind: course_batch_manager::CheckerKind::MultiPart,
+            reward_schedule: None,
+            parts,
+            attempt_policy: course_batch_manager::AttemptPolicy::default(),
+        },
+        f.course_authority.pubkey(),
+        f.course_pda,
+        checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        system_program::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    f.create_check_result(f.student_a.clone(), assignment_id, None)
+        .await?;
+
+    // the first part is correct, the second one is not
+    let check_result = f
+        .check_parts(
+            f.student_a.clone(),
+            checker_pda,
+            &[(0, answers[0].as_bytes()), (1, b"Komarov".as_ref())],
+        )
+        .await?;
+    assert_eq!(check_result.parts_passed, 0b001);
+    assert_eq!(check_result.check_passed, false);
+    let balance_a = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance_a.amount.as_str(), "10");
+
+    // the passed part is not paid twice
+    let check_result = f
+        .check_parts(
+            f.student_a.clone(),
+            checker_pda,
+            &[
+                (0, answers[0].as_bytes()),
+                (1, answers[1].as_bytes()),
+                (2, answers[2].as_bytes()),
+            ],
+        )
+        .await?;
+    assert_eq!(check_result.parts_passed, 0b111);
+    assert_eq!(check_result.check_passed, true);
+    assert_eq!(check_result.passed_first_time, true);
+    let balance_a = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance_a.amount.as_str(), "60");
+    let enrollment_a = f.get_enrollment_account(f.student_a.pubkey()).await?;
+    assert_eq!(enrollment_a.assignments_passed, 1);
+    assert_eq!(enrollment_a.tokens_earned, 60);
+
+    // the checker has no fourth part
+    assert!(f
+        .check_parts(f.student_a.clone(), checker_pda, &[(3, b"Leonov".as_ref())])
+        .await
+        .is_err());
+}
+
+/// Test if quiz choices committed before the deadline are graded against the revealed answer key
+#[trdelnik_test]
+async fn test_quiz_checker(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    let assignment_id = *b"rocket_quiz_____";
+    let answer_key = vec![2, 0, 1, 3];
+    let answer_key_nonce = [7; 32];
+    // the quiz is open for a few seconds
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    let deadline = now + 10;
+    let checker_pda =
+        course_batch_manager::assignment_checker_canonical_pda(f.course_pda, &assignment_id);
+    course_batch_manager_instruction::create_assignment_checker(
+        &f.client,
+        assignment_id,
+        None,
+        course_batch_manager::CheckerConfig {
+            hash_chain_length: 0,
+            to_mint_on_successful_check: 100,
+            salt: f.salt.clone(),
+            ground_truth_hash_chain_tail: [0; 32],
+            reveal_delay_slots: None,
+            kind: course_batch_manager::CheckerKind::Quiz {
+                answer_key_commitment: assignment_checker::answer_key_commitment(
+                    &answer_key,
+                    &answer_key_nonce,
+                ),
+                question_count: 4,
+            },
+            reward_schedule: Some(course_batch_manager::RewardSchedule {
+                due_at: deadline,
+                decay: course_batch_manager::RewardDecay::None,
+                cutoff_at: Some(deadline),
+            }),
+            parts: vec![],
+            attempt_policy: course_batch_manager::AttemptPolicy::default(),
+        },
+        f.course_authority.pubkey(),
+        f.course_pda,
+        checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        system_program::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    f.create_check_result(f.student_a.clone(), assignment_id, None)
+        .await?;
+    f.create_check_result(f.student_b.clone(), assignment_id, None)
+        .await?;
+
+    // student_a has 3 of 4 choices correct
+    let choices = vec![2, 0, 1, 0];
+    let nonce = [1; 32];
+    f.commit_assignment(
+        f.student_a.clone(),
+        checker_pda,
+        assignment_checker::quiz_commitment_hash(&f.student_a.pubkey(), &choices, &nonce),
+    )
+    .await?;
+    // the answer key is hidden until the deadline
+    assert!(f
+        .reveal_answer_key(checker_pda, answer_key.clone(), answer_key_nonce)
+        .await
+        .is_err());
+    assert!(f
+        .grade_quiz(f.student_a.clone(), checker_pda, choices.clone(), nonce)
+        .await
+        .is_err());
+
+    std::thread::sleep(Duration::from_secs(12));
+    // choices are not accepted after the deadline
+    assert!(f
+        .commit_assignment(
+            f.student_b.clone(),
+            checker_pda,
+            assignment_checker::quiz_commitment_hash(&f.student_b.pubkey(), &answer_key, &nonce),
+        )
+        .await
+        .is_err());
+    f.reveal_answer_key(checker_pda, answer_key.clone(), answer_key_nonce)
+        .await?;
+
+    let check_result = f
+        .grade_quiz(f.student_a.clone(), checker_pda, choices.clone(), nonce)
+        .await?;
+    assert_eq!(check_result.score, 3);
+    assert_eq!(check_result.check_passed, true);
+    let balance_a = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance_a.amount.as_str(), "75");
+
+    // the commitment is consumed by grading
+    assert!(f
+        .grade_quiz(f.student_a.clone(), checker_pda, choices, nonce)
+        .await
+        .is_err());
+}
+
+/// Test if batch-scoped checker has own hash chain and mints only into its batch
+#[trdelnik_test]
+async fn test_batch_scoped_checker(#[future] start_course_batch: Result<Fixture>) {
+    let mut f = start_course_batch.await?;
+    let first_batch_pda = f.course_batch_pda;
+
+    // the same assignment id gets a separate checker in the first batch
+    let reward = 50;
+    let checker_pda = course_batch_manager::batch_assignment_checker_canonical_pda(
+        f.course_pda,
+        first_batch_pda,
+        &f.assignment_id,
+    );
+    course_batch_manager_instruction::create_assignment_checker(
+        &f.client,
+        f.assignment_id,
+        Some(first_batch_pda),
+        course_batch_manager::CheckerConfig {
+            hash_chain_length: f.hash_chain_length,
+            to_mint_on_successful_check: reward,
+            salt: f.salt.clone(),
+            ground_truth_hash_chain_tail: Fixture::hash(
+                f.hash_chain_length,
+                &f.salt,
+                f.ground_truth_value.as_bytes(),
+            ),
+            reveal_delay_slots: None,
+            kind: course_batch_manager::CheckerKind::HashChain,
+            reward_schedule: None,
+            parts: vec![],
+            attempt_policy: course_batch_manager::AttemptPolicy::default(),
+        },
+        f.course_authority.pubkey(),
+        f.course_pda,
+        checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        system_program::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    f.create_check_result(f.student_a.clone(), f.assignment_id, Some(first_batch_pda))
+        .await?;
+
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            checker_pda,
+            f.course_pda,
+            first_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+    assert_eq!(check_result.passed_first_time, true);
+    let balance_a = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance_a.amount.as_str(), "50");
+    // the course-wide chain is not used
+    let checker_account = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert_eq!(checker_account.hash_chain_length, f.hash_chain_length);
+
+    // student_a enrolls the second batch of the course
+    f.batch_id = *b"the_second_batch";
+    f.course_batch_pda = course_batch_manager::batch_canonical_pda(f.course_pda, &f.batch_id);
+    f.course_batch_mint_pda =
+        course_batch_manager::batch_mint_canonical_pda(f.course_pda, &f.batch_id);
+    course_batch_manager_instruction::create_new_batch(
+        &f.client,
+        f.batch_id,
+        course_batch_manager::BatchSchedule {
+            enrollment_opens_at: 0,
+            enrollment_closes_at: i64::MAX,
+            starts_at: 0,
+            ends_at: i64::MAX,
+        },
+        course_batch_manager::EnrollmentPolicy::default(),
+        false,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        course_manager::course_metadata_canonical_pda(f.course_pda),
+        f.course_batch_pda,
+        f.course_batch_mint_pda,
+        system_program::ID,
+        rent::id(),
+        token::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    f.enroll_batch(f.student_a.clone(), vec![]).await?;
+    let second_batch_token_a =
+        get_associated_token_address(&f.student_a.pubkey(), &f.course_batch_mint_pda);
+
+    // the checker of the first batch can't be used in the second one
+    assert!(f
+        .create_check_result(
+            f.student_a.clone(),
+            *b"moon_hero_______",
+            Some(first_batch_pda)
+        )
+        .await
+        .is_err());
+    assert!(f
+        .check_assignment(
+            f.student_a.clone(),
+            second_batch_token_a,
+            checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await
+        .is_err());
+    let balance_a = f.client.get_token_balance(second_batch_token_a).await?;
+    assert_eq!(balance_a.amount.as_str(), "0");
+}
+
+/// Test if checks beyond the maximal number of attempts or within the cooldown are rejected
+#[trdelnik_test]
+async fn test_attempt_limits(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let ground_truth_hash_chain_tail = Fixture::hash(
+        f.hash_chain_length,
+        &f.salt,
+        f.ground_truth_value.as_bytes(),
+    );
+
+    // student_a has two attempts
+    let limited_id = *b"two_attempts____";
+    let limited_pda =
+        course_batch_manager::assignment_checker_canonical_pda(f.course_pda, &limited_id);
+    course_batch_manager_instruction::create_assignment_checker(
+        &f.client,
+        limited_id,
+        None,
+        course_batch_manager::CheckerConfig {
+            hash_chain_length: f.hash_chain_length,
+            to_mint_on_successful_check: 100,
+            salt: f.salt.clone(),
+            ground_truth_hash_chain_tail,
+            reveal_delay_slots: None,
+            kind: course_batch_manager::CheckerKind::HashChain,
+            reward_schedule: None,
+            parts: vec![],
+            attempt_policy: course_batch_manager::AttemptPolicy {
+                max_attempts: Some(2),
+                cooldown_slots: 0,
+            },
+        },
+        f.course_authority.pubkey(),
+        f.course_pda,
+        limited_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        system_program::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    f.create_check_result(f.student_a.clone(), limited_id, None)
+        .await?;
+
+    for attempt in 1..=2 {
+        let (_, check_result) = f
+            .check_assignment(
+                f.student_a.clone(),
+                f.student_a_token_account,
+                limited_pda,
+                f.course_pda,
+                f.course_batch_pda,
+                b"Laika",
+                None,
+            )
+            .await?;
+        assert_eq!(check_result.check_passed, false);
+        assert_eq!(check_result.attempts, attempt);
+        assert_eq!(check_result.passed_at, None);
+    }
+    // even the right answer is rejected after the last attempt
+    assert!(f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            limited_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await
+        .is_err());
+
+    // student_a waits long between attempts
+    let cooldown_id = *b"long_cooldown___";
+    let cooldown_pda =
+        course_batch_manager::assignment_checker_canonical_pda(f.course_pda, &cooldown_id);
+    course_batch_manager_instruction::create_assignment_checker(
+        &f.client,
+        cooldown_id,
+        None,
+        course_batch_manager::CheckerConfig {
+            hash_chain_length: f.hash_chain_length,
+            to_mint_on_successful_check: 100,
+            salt: f.salt.clone(),
+            ground_truth_hash_chain_tail,
+            reveal_delay_slots: None,
+            kind: course_batch_manager::CheckerKind::HashChain,
+            reward_schedule: None,
+            parts: vec![],
+            attempt_policy: course_batch_manager::AttemptPolicy {
+                max_attempts: None,
+                cooldown_slots: 1_000_000,
+            },
+        },
+        f.course_authority.pubkey(),
+        f.course_pda,
+        cooldown_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        system_program::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    f.create_check_result(f.student_a.clone(), cooldown_id, None)
+        .await?;
+
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            cooldown_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            b"Laika",
+            None,
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, false);
+    assert!(f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            cooldown_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await
+        .is_err());
+    let balance_a = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance_a.amount.as_str(), "0");
+}
+
+/// Test if paused checker rejects solutions until resumed and closed checker is removed
+#[trdelnik_test]
+async fn test_pause_and_close_checker(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+
+    // the answer has leaked
+    course_batch_manager_instruction::pause_assignment_checker(
+        &f.client,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let checker_account = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert!(checker_account.paused);
+    assert_eq!(checker_account.rent_payer, f.course_authority.pubkey());
+    assert!(f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await
+        .is_err());
+
+    // student can't resume the checker
+    assert!(course_batch_manager_instruction::resume_assignment_checker(
+        &f.client,
+        f.student_a.pubkey(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.student_a.clone()],
+    )
+    .await
+    .is_err());
+
+    course_batch_manager_instruction::resume_assignment_checker(
+        &f.client,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let (_, check_result) = f
+        .check_assignment(
+            f.student_a.clone(),
+            f.student_a_token_account,
+            f.assignment_checker_pda,
+            f.course_pda,
+            f.course_batch_pda,
+            f.ground_truth_value.as_bytes(),
+            None,
+        )
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+
+    // the checker is not needed anymore, check results are kept
+    course_batch_manager_instruction::close_assignment_checker(
+        &f.client,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        f.course_authority.pubkey(),
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    assert!(f
+        .get_checker_account(f.assignment_checker_pda)
+        .await
+        .is_err());
+    let check_result = f
+        .get_check_result_account(f.check_result_address(
+            f.student_a.pubkey(),
+            &f.assignment_id,
+            None,
+        ))
+        .await?;
+    assert_eq!(check_result.check_passed, true);
+
+    // the rent of a checker created by a co-instructor is returned to the co-instructor
+    let co_instructor = keypair(3);
+    f.client.airdrop(co_instructor.pubkey(), 5_000_000).await?;
+    f.add_co_instructor(co_instructor.pubkey()).await?;
+    let assignment_id = *b"co_instructor___";
+    let checker_pda =
+        course_batch_manager::assignment_checker_canonical_pda(f.course_pda, &assignment_id);
+    course_batch_manager_instruction::create_assignment_checker(
+        &f.client,
+        assignment_id,
+        None,
+        course_batch_manager::CheckerConfig {
+            hash_chain_length: f.hash_chain_length,
+            to_mint_on_successful_check: 100,
+            salt: f.salt.clone(),
+            ground_truth_hash_chain_tail: Fixture::hash(
+                f.hash_chain_length,
+                &f.salt,
+                f.ground_truth_value.as_bytes(),
+            ),
+            reveal_delay_slots: None,
+            kind: course_batch_manager::CheckerKind::HashChain,
+            reward_schedule: None,
+            parts: vec![],
+            attempt_policy: course_batch_manager::AttemptPolicy::default(),
+        },
+        co_instructor.pubkey(),
+        f.course_pda,
+        checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        system_program::ID,
+        [co_instructor.clone()],
+    )
+    .await?;
+    let checker_account = f.get_checker_account(checker_pda).await?;
+    assert_eq!(checker_account.rent_payer, co_instructor.pubkey());
+
+    // the course authority closing the checker can't take its rent
+    assert!(course_batch_manager_instruction::close_assignment_checker(
+        &f.client,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        checker_pda,
+        f.course_authority.pubkey(),
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.course_authority.clone()],
+    )
+    .await
+    .is_err());
+
+    course_batch_manager_instruction::close_assignment_checker(
+        &f.client,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        checker_pda,
+        co_instructor.pubkey(),
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    assert!(f.get_checker_account(checker_pda).await.is_err());
+}
+
+/// Test if the course authority is transferred only to the accepted proposal
+#[trdelnik_test]
+async fn test_authority_transfer(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let new_authority = keypair(4);
+    f.client.airdrop(new_authority.pubkey(), 5_000_000).await?;
+
+    // nothing to accept or cancel yet
+    assert!(course_manager_instruction::accept_authority(
+        &f.client,
+        new_authority.pubkey(),
+        f.course_pda,
+        [new_authority.clone()],
+    )
+    .await
+    .is_err());
+    assert!(course_manager_instruction::cancel_authority_transfer(
+        &f.client,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        [f.course_authority.clone()],
+    )
+    .await
+    .is_err());
+
+    course_manager_instruction::propose_authority(
+        &f.client,
+        new_authority.pubkey(),
+        f.course_authority.pubkey(),
+        f.course_pda,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let course_account = f.get_course_account().await?;
+    assert_eq!(
+        course_account.pending_authority,
+        Some(new_authority.pubkey())
+    );
+
+    // only the proposed authority can accept the course
+    assert!(course_manager_instruction::accept_authority(
+        &f.client,
+        f.student_a.pubkey(),
+        f.course_pda,
+        [f.student_a.clone()],
+    )
+    .await
+    .is_err());
+    // and only the current authority can cancel the proposal
+    assert!(course_manager_instruction::cancel_authority_transfer(
+        &f.client,
+        new_authority.pubkey(),
+        f.course_pda,
+        [new_authority.clone()],
+    )
+    .await
+    .is_err());
+
+    course_manager_instruction::cancel_authority_transfer(
+        &f.client,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let course_account = f.get_course_account().await?;
+    assert_eq!(course_account.pending_authority, None);
+    assert!(course_manager_instruction::accept_authority(
+        &f.client,
+        new_authority.pubkey(),
+        f.course_pda,
+        [new_authority.clone()],
+    )
+    .await
+    .is_err());
+
+    course_manager_instruction::propose_authority(
+        &f.client,
+        new_authority.pubkey(),
+        f.course_authority.pubkey(),
+        f.course_pda,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    course_manager_instruction::accept_authority(
+        &f.client,
+        new_authority.pubkey(),
+        f.course_pda,
+        [new_authority.clone()],
+    )
+    .await?;
+    let course_account = f.get_course_account().await?;
+    assert_eq!(course_account.authority, new_authority.pubkey());
+    assert_eq!(course_account.pending_authority, None);
+
+    // the course address is kept, the previous authority has lost its permissions
+    assert!(course_batch_manager_instruction::pause_assignment_checker(
+        &f.client,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [f.course_authority.clone()],
+    )
+    .await
+    .is_err());
+    course_batch_manager_instruction::pause_assignment_checker(
+        &f.client,
+        new_authority.pubkey(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [new_authority.clone()],
+    )
+    .await?;
+    let checker_account = f.get_checker_account(f.assignment_checker_pda).await?;
+    assert!(checker_account.paused);
+}
+
+/// Test if co-instructor manages batches but can't act as the course authority
+#[trdelnik_test]
+async fn test_co_instructor_permissions(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let co_instructor = keypair(3);
+    f.client.airdrop(co_instructor.pubkey(), 5_000_000).await?;
+    f.add_co_instructor(co_instructor.pubkey()).await?;
+    let enrollment_b =
+        course_batch_manager::enrollment_canonical_pda(f.student_b.pubkey(), f.course_batch_pda);
+
+    // co-instructor manages the batch
+    course_batch_manager_instruction::update_enrollment_policy(
+        &f.client,
+        course_batch_manager::EnrollmentPolicy {
+            max_seats: Some(2),
+            allow_list: course_batch_manager::AllowList::Open,
+        },
+        co_instructor.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        [co_instructor.clone()],
+    )
+    .await?;
+
+    // but can't expel students
+    assert!(course_batch_manager_instruction::expel_student(
+        &f.client,
+        co_instructor.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        enrollment_b,
+        f.course_batch_mint_pda,
+        f.student_b_token_account,
+        token::ID,
+        [co_instructor.clone()],
+    )
+    .await
+    .is_err());
+    // pause or close existing checkers
+    assert!(course_batch_manager_instruction::pause_assignment_checker(
+        &f.client,
+        co_instructor.pubkey(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [co_instructor.clone()],
+    )
+    .await
+    .is_err());
+    assert!(course_batch_manager_instruction::close_assignment_checker(
+        &f.client,
+        co_instructor.pubkey(),
+        f.course_pda,
+        f.assignment_checker_pda,
+        f.course_authority.pubkey(),
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        [co_instructor.clone()],
+    )
+    .await
+    .is_err());
+    // transfer the course or change the list of co-instructors
+    assert!(course_manager_instruction::propose_authority(
+        &f.client,
+        co_instructor.pubkey(),
+        co_instructor.pubkey(),
+        f.course_pda,
+        [co_instructor.clone()],
+    )
+    .await
+    .is_err());
+    assert!(course_manager_instruction::add_co_instructor(
+        &f.client,
+        f.student_a.pubkey(),
+        co_instructor.pubkey(),
+        f.course_pda,
+        [co_instructor.clone()],
+    )
+    .await
+    .is_err());
+    let enrollment_account = f.get_enrollment_account(f.student_b.pubkey()).await?;
+    assert_eq!(
+        enrollment_account.status,
+        course_batch_manager::EnrollmentStatus::Active
+    );
+
+    // removed co-instructor can't manage the batch anymore
+    course_manager_instruction::remove_co_instructor(
+        &f.client,
+        co_instructor.pubkey(),
+        f.course_authority.pubkey(),
+        f.course_pda,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    assert!(course_batch_manager_instruction::update_enrollment_policy(
+        &f.client,
+        course_batch_manager::EnrollmentPolicy::default(),
+        co_instructor.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        [co_instructor],
+    )
+    .await
+    .is_err());
+}
+
+/// Test if the batch mint metadata is created from the course metadata and refreshed with it
+///
+/// The local validator loads the token metadata program from `Anchor.toml`, see README
+#[trdelnik_test]
+async fn test_batch_token_metadata(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let course_metadata = course_manager::course_metadata_canonical_pda(f.course_pda);
+
+    // the first call creates the metadata account, the next one updates it
+    for (version, title) in [(1, "From Web2 to Web3"), (2, "Web3 for Web2 developers")] {
+        let content_uri = format!("https://example.com/syllabus_v{}.json", version);
+        course_manager_instruction::update_course_metadata(
+            &f.client,
+            title.to_string(),
+            content_uri.clone(),
+            [version; 32],
+            version.into(),
+            f.course_authority.pubkey(),
+            f.course_pda,
+            course_metadata,
+            [f.course_authority.clone()],
+        )
+        .await?;
+        course_batch_manager_instruction::set_batch_token_metadata(
+            &f.client,
+            f.course_authority.pubkey(),
+            f.course_pda,
+            course_metadata,
+            f.course_batch_pda,
+            f.course_batch_mint_pda,
+            Fixture::token_metadata_address(f.course_batch_mint_pda),
+            mpl_token_metadata::ID,
+            system_program::ID,
+            rent::id(),
+            [f.course_authority.clone()],
+        )
+        .await?;
+
+        let metadata = f.get_token_metadata(f.course_batch_mint_pda).await?;
+        assert_eq!(metadata.mint, f.course_batch_mint_pda);
+        assert_eq!(metadata.update_authority, f.course_batch_pda);
+        assert_eq!(
+            metadata.data.name.trim_end_matches('\0'),
+            course_batch_manager::batch_token_name(title, &f.batch_id)
+        );
+        assert_eq!(metadata.data.symbol.trim_end_matches('\0'), "WEB2_TO_WE");
+        assert_eq!(metadata.data.uri.trim_end_matches('\0'), content_uri);
+    }
+}
+
+/// Test if the certificate is issued once to the student who has earned enough tokens
+///
+/// The local validator loads the token metadata program from `Anchor.toml`, see README
+#[trdelnik_test]
+async fn test_claim_certificate(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    f.create_certificate_policy(course_batch_manager::CertificateRequirement::MinTokens {
+        amount: 100,
+    })
+    .await?;
+
+    // student_a has earned no tokens yet
+    assert!(f.claim_certificate(f.student_a.clone(), &[]).await.is_err());
+
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+    f.claim_certificate(f.student_a.clone(), &[]).await?;
+    let enrollment = f.get_enrollment_account(f.student_a.pubkey()).await?;
+    assert_eq!(
+        enrollment.status,
+        course_batch_manager::EnrollmentStatus::Completed
+    );
+    let certificate = f.get_certificate_account(f.student_a.pubkey()).await?;
+    assert_eq!(certificate.course_batch, f.course_batch_pda);
+    assert_eq!(certificate.student, f.student_a.pubkey());
+    assert_eq!(certificate.final_score, 100);
+    let certificate_token = get_associated_token_address(&f.student_a.pubkey(), &certificate.mint);
+    let balance = f.client.get_token_balance(certificate_token).await?;
+    assert_eq!(balance.amount.as_str(), "1");
+    let metadata = f.get_token_metadata(certificate.mint).await?;
+    assert_eq!(metadata.update_authority, f.course_batch_pda);
+    assert_eq!(
+        metadata.data.uri.trim_end_matches('\0'),
+        "https://example.com/certificate.json"
+    );
+    let reputation = f.get_reputation_account(f.student_a.pubkey()).await?;
+    assert_eq!(reputation.courses_completed, 1);
+    assert!(reputation.courses[0].completed);
+
+    // the certificate can't be claimed twice
+    assert!(f.claim_certificate(f.student_a.clone(), &[]).await.is_err());
+}
+
+/// Test if the certificate requires the passed check results of the student for all assignments
+///
+/// The local validator loads the token metadata program from `Anchor.toml`, see README
+#[trdelnik_test]
+async fn test_claim_certificate_for_passed_assignments(
+    #[future] start_course_batch: Result<Fixture>,
+) {
+    let f = start_course_batch.await?;
+
+    // the second assignment has the same answer as the first one
+    let second_assignment_id = *b"second_hero_____";
+    let second_checker_pda =
+        course_batch_manager::assignment_checker_canonical_pda(f.course_pda, &second_assignment_id);
+    course_batch_manager_instruction::create_assignment_checker(
+        &f.client,
+        second_assignment_id,
+        None,
+        course_batch_manager::CheckerConfig {
+            hash_chain_length: f.hash_chain_length,
+            to_mint_on_successful_check: 100,
+            salt: f.salt.clone(),
+            ground_truth_hash_chain_tail: Fixture::hash(
+                f.hash_chain_length,
+                &f.salt,
+                f.ground_truth_value.as_bytes(),
+            ),
+            reveal_delay_slots: None,
+            kind: course_batch_manager::CheckerKind::HashChain,
+            reward_schedule: None,
+            parts: vec![],
+            attempt_policy: course_batch_manager::AttemptPolicy::default(),
+        },
+        f.course_authority.pubkey(),
+        f.course_pda,
+        second_checker_pda,
+        assignment_checker::ID,
+        course_batch_manager::ID,
+        system_program::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    f.create_check_result(f.student_a.clone(), second_assignment_id, None)
+        .await?;
+    f.create_check_result(f.student_b.clone(), second_assignment_id, None)
+        .await?;
+    f.create_certificate_policy(
+        course_batch_manager::CertificateRequirement::PassedAssignments {
+            assignment_ids: vec![f.assignment_id, second_assignment_id],
+        },
+    )
+    .await?;
+
+    // student_a passes the first assignment, student_b passes the second one
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+    f.check_assignment(
+        f.student_b.clone(),
+        f.student_b_token_account,
+        second_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+    let first_result_a = f.check_result_address(f.student_a.pubkey(), &f.assignment_id, None);
+    let second_result_a = f.check_result_address(f.student_a.pubkey(), &second_assignment_id, None);
+    let second_result_b = f.check_result_address(f.student_b.pubkey(), &second_assignment_id, None);
+
+    // the check result of the second assignment is missing
+    assert!(f
+        .claim_certificate(f.student_a.clone(), &[first_result_a])
+        .await
+        .is_err());
+    // the check result of the second assignment is not passed
+    assert!(f
+        .claim_certificate(f.student_a.clone(), &[first_result_a, second_result_a])
+        .await
+        .is_err());
+    // the passed check result of student_b is forged for student_a
+    assert!(f
+        .claim_certificate(f.student_a.clone(), &[first_result_a, second_result_b])
+        .await
+        .is_err());
+    // the check results are not in the order of the requirement
+    assert!(f
+        .claim_certificate(f.student_a.clone(), &[second_result_a, first_result_a])
+        .await
+        .is_err());
+
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        second_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+    f.claim_certificate(f.student_a.clone(), &[first_result_a, second_result_a])
+        .await?;
+    let enrollment = f.get_enrollment_account(f.student_a.pubkey()).await?;
+    assert_eq!(
+        enrollment.status,
+        course_batch_manager::EnrollmentStatus::Completed
+    );
+    let certificate = f.get_certificate_account(f.student_a.pubkey()).await?;
+    assert_eq!(certificate.final_score, 200);
+
+    // the certificate can't be claimed twice
+    assert!(f
+        .claim_certificate(f.student_a.clone(), &[first_result_a, second_result_a])
+        .await
+        .is_err());
+}
+
+/// Test if batch tokens are burned for a catalog item and the receipt is fulfilled
+#[trdelnik_test]
+async fn test_redeem(#[future] start_course_batch: Result<Fixture>) {
+    let f = start_course_batch.await?;
+    let item_id = *b"mentoring_call__";
+    let catalog_item =
+        course_batch_manager::catalog_item_canonical_pda(f.course_batch_pda, &item_id);
+
+    // a single call with a mentor for 60 tokens
+    course_batch_manager_instruction::create_catalog_item(
+        &f.client,
+        item_id,
+        60,
+        1,
+        "https://example.com/mentoring_call.json".to_string(),
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        catalog_item,
+        system_program::ID,
+        [f.course_authority.clone()],
+    )
+    .await?;
+
+    f.check_assignment(
+        f.student_a.clone(),
+        f.student_a_token_account,
+        f.assignment_checker_pda,
+        f.course_pda,
+        f.course_batch_pda,
+        f.ground_truth_value.as_bytes(),
+        None,
+    )
+    .await?;
+    let redemption = course_batch_manager::redemption_canonical_pda(catalog_item, 0);
+    course_batch_manager_instruction::redeem(
+        &f.client,
+        f.student_a.pubkey(),
+        f.course_batch_pda,
+        course_batch_manager::enrollment_canonical_pda(f.student_a.pubkey(), f.course_batch_pda),
+        catalog_item,
+        redemption,
+        f.course_batch_mint_pda,
+        f.student_a_token_account,
+        system_program::ID,
+        token::ID,
+        [f.student_a.clone()],
+    )
+    .await?;
+    let balance_a = f
+        .client
+        .get_token_balance(f.student_a_token_account)
+        .await?;
+    assert_eq!(balance_a.amount.as_str(), "40");
+
+    // the only item is redeemed
+    assert!(course_batch_manager_instruction::redeem(
+        &f.client,
+        f.student_a.pubkey(),
+        f.course_batch_pda,
+        course_batch_manager::enrollment_canonical_pda(f.student_a.pubkey(), f.course_batch_pda),
+        catalog_item,
+        course_batch_manager::redemption_canonical_pda(catalog_item, 1),
+        f.course_batch_mint_pda,
+        f.student_a_token_account,
+        system_program::ID,
+        token::ID,
+        [f.student_a.clone()],
+    )
+    .await
+    .is_err());
+
+    // co-instructor can't fulfill the redemption
+    let co_instructor = keypair(3);
+    f.add_co_instructor(co_instructor.pubkey()).await?;
+    assert!(course_batch_manager_instruction::fulfill_redemption(
+        &f.client,
+        co_instructor.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        catalog_item,
+        redemption,
+        [co_instructor],
+    )
+    .await
+    .is_err());
+
+    course_batch_manager_instruction::fulfill_redemption(
+        &f.client,
+        f.course_authority.pubkey(),
+        f.course_pda,
+        f.course_batch_pda,
+        catalog_item,
+        redemption,
+        [f.course_authority.clone()],
+    )
+    .await?;
+    let redemption_account = f
+        .client
+        .account_data::<course_batch_manager::Redemption>(redemption)
+        .await?;
+    assert_eq!(redemption_account.cost, 60);
+    assert!(redemption_account.fulfilled_at.is_some());
+}
+
+/// Metaplex metadata account readable by `Client::account_data`
+struct TokenMetadata(Metadata);
+
+impl AccountDeserialize for TokenMetadata {
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+        Metadata::safe_deserialize(buf)
+            .map(TokenMetadata)
+            .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
+
+/// Input keypairs / pubkeys / programs and data to configure tests
+struct Fixture {
+    client: Client,
+    assignment_checker_program: Keypair,
+    course_program: Keypair,
+    course_batch_program: Keypair,
+    reputation_program: Keypair,
+
+    course_authority: Keypair,
+    course_id: [u8; 16],
+    course_pda: Pubkey,
+
+    batch_id: [u8; 16],
+    course_batch_pda: Pubkey,
+    course_batch_mint_pda: Pubkey,
+
+    assignment_id: [u8; 16],
+    ground_truth_value: String,
+    hash_chain_length: u16,
+    salt: [u8; 32],
+    assignment_checker_pda: Pubkey,
+
+    // can be related to course_authority
+    student_a: Keypair,
+    student_a_token_account: Pubkey,
+    student_b: Keypair,
+    student_b_token_account: Pubkey,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        Fixture {
+            client: Client::new(system_keypair(0)),
+            assignment_checker_program: program_keypair(1),
+            course_program: program_keypair(2),
+            course_batch_program: program_keypair(3),
+            // keypair of the address declared in the reputation program
+            reputation_program: Keypair::from_bytes(&[
+                200, 218, 17, 146, 99, 85, 90, 31, 61, 116, 228, 126, 140, 166, 55, 81, 112, 217,
+                157, 200, 165, 114, 117, 119, 119, 194, 22, 16, 83, 86, 51, 26, 5, 214, 221, 174,
+                249, 185, 52, 147, 1, 69, 81, 4, 20, 38, 73, 168, 254, 160, 214, 233, 0, 185, 104,
+                21, 183, 60, 84, 142, 21, 80, 126, 19,
+            ])
+            .expect("valid reputation program keypair"),
+            course_authority: keypair(0),
+            course_id: *b"web2_to_web3____",
+            course_pda: Pubkey::default(),
+            batch_id: *b"the_first_batch_",
+            course_batch_pda: Pubkey::default(),
+            course_batch_mint_pda: Pubkey::default(),
+            assignment_id: *b"space_hero______",
+            // Assignment: "Surname of the first man in space"
+            ground_truth_value: "Gagarin".to_string(),
+            // support up to 10 - 1 students
+            hash_chain_length: 10,
+            // good enough for test
+            salt: [0; 32],
+            assignment_checker_pda: Pubkey::default(),
+
+            student_a: keypair(1),
+            student_a_token_account: Pubkey::default(),
+            student_b: keypair(2),
+            student_b_token_account: Pubkey::default(),
+        }
+    }
+
+    #[throws]
+    async fn deploy(&mut self) {
+        self.client
+            .deploy_by_name(&self.course_program, "course_manager")
+            .await?;
+        self.client
+            .deploy_by_name(&self.course_batch_program, "course_batch_manager")
+            .await?;
+        self.client
+            .deploy_by_name(&self.assignment_checker_program, "assignment_checker")
+            .await?;
+        self.client
+            .deploy_by_name(&self.reputation_program, "reputation")
+            .await?;
+    }
+
+    #[throws]
+    async fn get_course_account(&self) -> course_manager::Course {
+        self.client
+            .account_data::<course_manager::Course>(self.course_pda)
+            .await?
+    }
+
+    /// Metaplex metadata account of the mint
+    fn token_metadata_address(mint: Pubkey) -> Pubkey {
+        let metadata_seeds = [
+            b"metadata".as_ref(),
+            mpl_token_metadata::ID.as_ref(),
+            mint.as_ref(),
+        ];
+        Pubkey::find_program_address(&metadata_seeds, &mpl_token_metadata::ID).0
+    }
+
+    #[throws]
+    async fn get_token_metadata(&self, mint: Pubkey) -> Metadata {
+        self.client
+            .account_data::<TokenMetadata>(Self::token_metadata_address(mint))
+            .await?
+            .0
+    }
+
+    #[throws]
+    async fn get_certificate_account(&self, student: Pubkey) -> course_batch_manager::Certificate {
+        let enrollment =
+            course_batch_manager::enrollment_canonical_pda(student, self.course_batch_pda);
+        self.client
+            .account_data::<course_batch_manager::Certificate>(
+                course_batch_manager::certificate_canonical_pda(enrollment),
+            )
+            .await?
+    }
+
+    #[throws]
+    async fn get_course_batch_account(&self) -> course_batch_manager::CourseBatch {
+        self.client
+            .account_data::<course_batch_manager::CourseBatch>(self.course_batch_pda)
+            .await?
+    }
+
+    #[throws]
+    async fn get_enrollment_account(&self, student: Pubkey) -> course_batch_manager::Enrollment {
+        self.client
+            .account_data::<course_batch_manager::Enrollment>(
+                course_batch_manager::enrollment_canonical_pda(student, self.course_batch_pda),
+            )
+            .await?
+    }
+
+    #[throws]
+    async fn get_reputation_account(&self, student: Pubkey) -> reputation::Reputation {
+        self.client
+            .account_data::<reputation::Reputation>(reputation::reputation_canonical_pda(student))
+            .await?
+    }
+
+    #[throws]
+    async fn get_checker_account(
+        &self,
+        checker_pda: Pubkey,
+    ) -> assignment_checker::AssignmentCheckerState {
+        self.client
+            .account_data::<course_batch_manager::AssignmentCheckerState>(checker_pda)
+            .await?
+    }
+
+    #[throws]
+    async fn get_check_result_account(
+        &self,
+        check_result_pda: Pubkey,
+    ) -> assignment_checker::CheckResult {
+        self.client
+            .account_data::<course_batch_manager::CheckResult>(check_result_pda)
+            .await?
+    }
+
+    #[throws]
+    async fn add_co_instructor(&self, co_instructor: Pubkey) {
+        course_manager_instruction::add_co_instructor(
+            &self.client,
+            co_instructor,
+            self.course_authority.pubkey(),
+            self.course_pda,
+            [self.course_authority.clone()],
+        )
+        .await?;
+    }
+
+    #[throws]
+    async fn create_batch(
+        &mut self,
+        batch_id: [u8; 16],
+        schedule: course_batch_manager::BatchSchedule,
+    ) {
+        let course_batch_pda =
+            course_batch_manager::batch_canonical_pda(self.course_pda, &batch_id);
+        let course_batch_mint_pda =
+            course_batch_manager::batch_mint_canonical_pda(self.course_pda, &batch_id);
+        course_batch_manager_instruction::create_new_batch(
+            &self.client,
+            batch_id,
+            schedule,
+            course_batch_manager::EnrollmentPolicy::default(),
+            false,
+            self.course_authority.pubkey(),
+            self.course_pda,
+            course_manager::course_metadata_canonical_pda(self.course_pda),
+            course_batch_pda,
+            course_batch_mint_pda,
+            system_program::ID,
+            rent::id(),
+            token::ID,
+            [self.course_authority.clone()],
+        )
+        .await?;
+        self.batch_id = batch_id;
+        self.course_batch_pda = course_batch_pda;
+        self.course_batch_mint_pda = course_batch_mint_pda;
+    }
+
+    #[throws]
+    async fn enroll_batch(&self, student_keypair: Keypair, allow_list_proof: Vec<[u8; 32]>) {
+        course_batch_manager_instruction::enroll_batch(
+            &self.client,
+            allow_list_proof,
+            student_keypair.pubkey(),
+            self.course_batch_pda,
+            course_batch_manager::enrollment_canonical_pda(
+                student_keypair.pubkey(),
+                self.course_batch_pda,
+            ),
+            self.course_batch_mint_pda,
+            get_associated_token_address(&student_keypair.pubkey(), &self.course_batch_mint_pda),
+            system_program::ID,
+            token::ID,
+            associated_token::ID,
+            rent::id(),
+            instructions::ID,
+            [student_keypair],
+        )
+        .await?;
+    }
+
+    #[throws]
+    async fn init_registry(&self, authority: Keypair) {
+        reputation_instruction::init_config(
+            &self.client,
+            authority.pubkey(),
+            reputation::config_canonical_pda(),
+            reputation::ID,
+            reputation::program_data_address(),
+            system_program::ID,
+            [authority],
+        )
+        .await?;
+    }
+
+    #[throws]
+    async fn init_reputation(&self, student: Pubkey) {
+        reputation_instruction::init_reputation(
+            &self.client,
+            student,
+            self.course_authority.pubkey(),
+            reputation::reputation_canonical_pda(student),
+            system_program::ID,
+            [self.course_authority.clone()],
+        )
+        .await?;
+    }
+
+    #[throws]
+    async fn create_certificate_policy(
+        &self,
+        requirement: course_batch_manager::CertificateRequirement,
+    ) {
+        course_batch_manager_instruction::create_certificate_policy(
+            &self.client,
+            requirement,
+            "https://example.com/certificate.json".to_string(),
+            self.course_authority.pubkey(),
+            self.course_pda,
+            self.course_batch_pda,
+            course_batch_manager::certificate_policy_canonical_pda(self.course_batch_pda),
+            system_program::ID,
+            [self.course_authority.clone()],
+        )
+        .await?;
+    }
+
+    /// Claim the certificate with the check results of the required assignments
+    ///
+    /// The check results are remaining accounts of the instruction, so it is built here
+    #[throws]
+    async fn claim_certificate(&self, student_keypair: Keypair, check_results: &[Pubkey]) {
+        let student = student_keypair.pubkey();
+        let enrollment =
+            course_batch_manager::enrollment_canonical_pda(student, self.course_batch_pda);
+        let certificate_mint = course_batch_manager::certificate_mint_canonical_pda(enrollment);
+        let edition_seeds = [
+            b"metadata".as_ref(),
+            mpl_token_metadata::ID.as_ref(),
+            certificate_mint.as_ref(),
+            b"edition".as_ref(),
+        ];
+        let mut accounts = course_batch_manager::accounts::ClaimCertificate {
+            student,
+            course: self.course_pda,
+            course_batch: self.course_batch_pda,
+            certificate_policy: course_batch_manager::certificate_policy_canonical_pda(
+                self.course_batch_pda,
+            ),
+            enrollment,
+            certificate: course_batch_manager::certificate_canonical_pda(enrollment),
+            certificate_mint,
+            certificate_token: get_associated_token_address(&student, &certificate_mint),
+            certificate_metadata: Self::token_metadata_address(certificate_mint),
+            certificate_master_edition: Pubkey::find_program_address(
+                &edition_seeds,
+                &mpl_token_metadata::ID,
+            )
+            .0,
+            token_metadata_program: mpl_token_metadata::ID,
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            rent: rent::id(),
+            reputation_config: reputation::config_canonical_pda(),
+            reputation: reputation::reputation_canonical_pda(student),
+            reputation_writer: reputation::writer_signer_address(&course_batch_manager::ID),
+            reputation_program: reputation::ID,
+        }
+        .to_account_metas(None);
+        accounts.extend(
+            check_results
+                .iter()
+                .map(|check_result| AccountMeta::new_readonly(*check_result, false)),
+        );
+        let claim = Instruction {
+            program_id: course_batch_manager::ID,
+            accounts,
+            data: course_batch_manager::instruction::ClaimCertificate {}.data(),
+        };
+        self.client
+            .send_transaction(&[claim], [&student_keypair])
+            .await?;
+    }
+
+    #[throws]
+    async fn create_check_result(
+        &self,
+        student_keypair: Keypair,
+        assignment_id: [u8; 16],
+        scope: Option<Pubkey>,
+    ) {
+        let check_result =
+            self.check_result_address(student_keypair.pubkey(), &assignment_id, scope);
+        course_batch_manager_instruction::create_check_result(
+            &self.client,
+            assignment_id,
+            scope,
+            student_keypair.pubkey(),
+            self.course_pda,
+            self.course_batch_pda,
+            course_batch_manager::enrollment_canonical_pda(
+                student_keypair.pubkey(),
+                self.course_batch_pda,
+            ),
+            check_result,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            system_program::ID,
+            [student_keypair],
+        )
+        .await?;
+    }
+
+    /// Checks student-bound assignment and returns the result of the check
+    #[throws]
+    async fn check_student_bound(
+        &self,
+        student_keypair: Keypair,
+        checker_data_address: Pubkey,
+        answer_hash: [u8; 32],
+        proof: Vec<[u8; 32]>,
+    ) -> course_batch_manager::CheckResult {
+        let assignment_checker = self.get_checker_account(checker_data_address).await?;
+        let student_token_address =
+            get_associated_token_address(&student_keypair.pubkey(), &self.course_batch_mint_pda);
+        let check_result_address = self.check_result_address(
+            student_keypair.pubkey(),
+            &assignment_checker.assignment_id,
+            assignment_checker.scope,
+        );
+
+        course_batch_manager_instruction::check_assignment(
+            &self.client,
+            course_batch_manager::Submission::StudentBound { answer_hash, proof },
+            student_keypair.pubkey(),
+            self.course_pda,
+            self.course_batch_pda,
+            course_batch_manager::enrollment_canonical_pda(
+                student_keypair.pubkey(),
+                self.course_batch_pda,
+            ),
+            checker_data_address,
+            check_result_address,
+            self.course_batch_mint_pda,
+            student_token_address,
+            system_program::ID,
+            token::ID,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            instructions::ID,
+            reputation::config_canonical_pda(),
+            reputation::reputation_canonical_pda(student_keypair.pubkey()),
+            reputation::writer_signer_address(&course_batch_manager::ID),
+            reputation::ID,
+            [student_keypair],
+        )
+        .await?;
+        self.get_check_result_account(check_result_address).await?
+    }
+
+    /// Send the score signed by the grader in the ed25519 program instruction before the check
+    #[throws]
+    async fn check_grader_attestation(
+        &self,
+        student_keypair: Keypair,
+        checker_data_address: Pubkey,
+        grader: &Keypair,
+        score: u16,
+    ) -> course_batch_manager::CheckResult {
+        let student = student_keypair.pubkey();
+        let check_result_address = self.check_result_address(
+            student,
+            &self
+                .get_checker_account(checker_data_address)
+                .await?
+                .assignment_id,
+            None,
+        );
+        let message =
+            assignment_checker::grader_attestation_message(&checker_data_address, &student, score);
+        let check = Instruction {
+            program_id: course_batch_manager::ID,
+            accounts: course_batch_manager::accounts::CheckAssignment {
+                student,
+                course: self.course_pda,
+                course_batch: self.course_batch_pda,
+                enrollment: course_batch_manager::enrollment_canonical_pda(
+                    student,
+                    self.course_batch_pda,
+                ),
+                assignment_checker: checker_data_address,
+                check_result: check_result_address,
+                mint: self.course_batch_mint_pda,
+                course_batch_token: get_associated_token_address(
+                    &student,
+                    &self.course_batch_mint_pda,
+                ),
+                system_program: system_program::ID,
+                token_program: token::ID,
+                assignment_checker_program: assignment_checker::ID,
+                course_batch_manager_program: course_batch_manager::ID,
+                instructions: instructions::ID,
+                reputation_config: reputation::config_canonical_pda(),
+                reputation: reputation::reputation_canonical_pda(student),
+                reputation_writer: reputation::writer_signer_address(&course_batch_manager::ID),
+                reputation_program: reputation::ID,
+            }
+            .to_account_metas(None),
+            data: course_batch_manager::instruction::CheckAssignment {
+                submission: course_batch_manager::Submission::GraderAttestation { score },
+            }
+            .data(),
+        };
+        self.client
+            .send_transaction(
+                &[Self::ed25519_instruction(grader, &message), check],
+                [&student_keypair],
+            )
+            .await?;
+        self.get_check_result_account(check_result_address).await?
+    }
+
+    /// Ed25519 program instruction verifying the signature of the message by the signer
+    fn ed25519_instruction(signer: &Keypair, message: &[u8]) -> Instruction {
+        // the signature, public key and message are in the instruction itself
+        const CURRENT_INSTRUCTION: u16 = u16::MAX;
+        const PUBLIC_KEY_OFFSET: u16 = 16;
+        const SIGNATURE_OFFSET: u16 = PUBLIC_KEY_OFFSET + 32;
+        const MESSAGE_OFFSET: u16 = SIGNATURE_OFFSET + 64;
+        let offsets = [
+            SIGNATURE_OFFSET,
+            CURRENT_INSTRUCTION,
+            PUBLIC_KEY_OFFSET,
+            CURRENT_INSTRUCTION,
+            MESSAGE_OFFSET,
+            message.len() as u16,
+            CURRENT_INSTRUCTION,
+        ];
+        let mut data = vec![1, 0];
+        for offset in offsets {
+            data.extend_from_slice(&offset.to_le_bytes());
+        }
+        data.extend_from_slice(signer.pubkey().as_ref());
+        data.extend_from_slice(signer.sign_message(message).as_ref());
+        data.extend_from_slice(message);
+        Instruction {
+            program_id: ed25519_program::ID,
+            accounts: vec![],
+            data,
+        }
+    }
+
+    #[throws]
+    async fn commit_assignment(
+        &self,
+        student_keypair: Keypair,
+        checker_data_address: Pubkey,
+        commitment: [u8; 32],
+    ) {
+        let assignment_checker = self.get_checker_account(checker_data_address).await?;
+        course_batch_manager_instruction::commit_assignment(
+            &self.client,
+            commitment,
+            student_keypair.pubkey(),
+            self.course_pda,
+            checker_data_address,
+            self.check_result_address(
+                student_keypair.pubkey(),
+                &assignment_checker.assignment_id,
+                assignment_checker.scope,
+            ),
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [student_keypair],
+        )
+        .await?;
+    }
+
+    #[throws]
+    async fn reveal_answer_key(
+        &self,
+        checker_data_address: Pubkey,
+        answer_key: Vec<u8>,
+        nonce: [u8; 32],
+    ) {
+        course_batch_manager_instruction::reveal_answer_key(
+            &self.client,
+            answer_key,
+            nonce,
+            self.course_authority.pubkey(),
+            self.course_pda,
+            checker_data_address,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            [self.course_authority.clone()],
+        )
+        .await?;
+    }
+
+    /// Reveals committed hash chain tail parent and returns the result of the check
+    #[throws]
+    async fn reveal_assignment(
+        &self,
+        student_keypair: Keypair,
+        checker_data_address: Pubkey,
+        hash_chain_tail_parent: [u8; 32],
+        nonce: [u8; 32],
+    ) -> course_batch_manager::CheckResult {
+        let assignment_checker = self.get_checker_account(checker_data_address).await?;
+        let check_result_address = self.check_result_address(
+            student_keypair.pubkey(),
+            &assignment_checker.assignment_id,
+            assignment_checker.scope,
+        );
+
+        course_batch_manager_instruction::reveal_assignment(
+            &self.client,
+            assignment_checker.hash_chain_length,
+            hash_chain_tail_parent,
+            nonce,
+            student_keypair.pubkey(),
+            self.course_pda,
+            self.course_batch_pda,
+            course_batch_manager::enrollment_canonical_pda(
+                student_keypair.pubkey(),
+                self.course_batch_pda,
+            ),
+            checker_data_address,
+            check_result_address,
+            self.course_batch_mint_pda,
+            get_associated_token_address(&student_keypair.pubkey(), &self.course_batch_mint_pda),
+            system_program::ID,
+            token::ID,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            instructions::ID,
+            reputation::config_canonical_pda(),
+            reputation::reputation_canonical_pda(student_keypair.pubkey()),
+            reputation::writer_signer_address(&course_batch_manager::ID),
+            reputation::ID,
+            [student_keypair],
+        )
+        .await?;
+        self.get_check_result_account(check_result_address).await?
+    }
+
+    /// Grades committed quiz choices and returns the result of the check
+    #[throws]
+    async fn grade_quiz(
+        &self,
+        student_keypair: Keypair,
+        checker_data_address: Pubkey,
+        choices: Vec<u8>,
+        nonce: [u8; 32],
+    ) -> course_batch_manager::CheckResult {
+        let assignment_checker = self.get_checker_account(checker_data_address).await?;
+        let check_result_address = self.check_result_address(
+            student_keypair.pubkey(),
+            &assignment_checker.assignment_id,
+            assignment_checker.scope,
+        );
+
+        course_batch_manager_instruction::grade_quiz(
+            &self.client,
+            choices,
+            nonce,
+            student_keypair.pubkey(),
+            self.course_pda,
+            self.course_batch_pda,
+            course_batch_manager::enrollment_canonical_pda(
+                student_keypair.pubkey(),
+                self.course_batch_pda,
+            ),
+            checker_data_address,
+            check_result_address,
+            self.course_batch_mint_pda,
+            get_associated_token_address(&student_keypair.pubkey(), &self.course_batch_mint_pda),
+            system_program::ID,
+            token::ID,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            instructions::ID,
+            reputation::config_canonical_pda(),
+            reputation::reputation_canonical_pda(student_keypair.pubkey()),
+            reputation::writer_signer_address(&course_batch_manager::ID),
+            reputation::ID,
+            [student_keypair],
+        )
+        .await?;
+        self.get_check_result_account(check_result_address).await?
+    }
+
+    /// Checks parts of multi-part assignment and returns the result of the check
+    #[throws]
+    async fn check_parts(
+        &self,
+        student_keypair: Keypair,
+        checker_data_address: Pubkey,
+        // (part index, value to check)
+        values_to_check: &[(u8, &[u8])],
+    ) -> course_batch_manager::CheckResult {
+        let assignment_checker = self.get_checker_account(checker_data_address).await?;
+        let parts = values_to_check
+            .iter()
+            .map(|(index, value_to_check)| {
+                // a missing part is sent as is to be rejected by the checker
+                let hash_chain_length = assignment_checker
+                    .parts
+                    .get(usize::from(*index))
+                    .map_or(self.hash_chain_length, |part| part.hash_chain_length);
+                course_batch_manager::PartSubmission {
+                    index: *index,
+                    expected_hash_chain_length: hash_chain_length,
+                    hash_chain_tail_parent: Self::hash(
+                        hash_chain_length - 1,
+                        &assignment_checker.salt,
+                        value_to_check,
+                    ),
+                }
+            })
+            .collect();
+        let check_result_address = self.check_result_address(
+            student_keypair.pubkey(),
+            &assignment_checker.assignment_id,
+            assignment_checker.scope,
+        );
+
+        course_batch_manager_instruction::check_assignment(
+            &self.client,
+            course_batch_manager::Submission::MultiPart { parts },
+            student_keypair.pubkey(),
+            self.course_pda,
+            self.course_batch_pda,
+            course_batch_manager::enrollment_canonical_pda(
+                student_keypair.pubkey(),
+                self.course_batch_pda,
+            ),
+            checker_data_address,
+            check_result_address,
+            self.course_batch_mint_pda,
+            get_associated_token_address(&student_keypair.pubkey(), &self.course_batch_mint_pda),
+            system_program::ID,
+            token::ID,
+            assignment_checker::ID,
+            course_batch_manager::ID,
+            instructions::ID,
+            reputation::config_canonical_pda(),
+            reputation::reputation_canonical_pda(student_keypair.pubkey()),
+            reputation::writer_signer_address(&course_batch_manager::ID),
+            reputation::ID,
+            [student_keypair],
+        )
+        .await?;
+        self.get_check_result_account(check_result_address).await?
+    }
 
     /// Checks assignment and returns the hashed value_to_check and the result of the check
     #[throws]
@@ -371,27 +3195,42 @@ impl Fixture {
             )
         });
 
-        let check_result_address = course_batch_manager::check_result_canonical_pda(
+        let check_result_address = self.check_result_address(
             student_keypair.pubkey(),
-            course_data_address,
             &assignment_checker.assignment_id,
+            assignment_checker.scope,
         );
+        let course_batch = self
+            .client
+            .account_data::<course_batch_manager::CourseBatch>(course_batch_address)
+            .await?;
 
         course_batch_manager_instruction::check_assignment(
             &self.client,
-            hash_chain_length,
-            hash_chain_tail_parent,
+            course_batch_manager::Submission::HashChain {
+                expected_hash_chain_length: hash_chain_length,
+                hash_chain_tail_parent,
+            },
             student_keypair.pubkey(),
             course_data_address,
             course_batch_address,
+            course_batch_manager::enrollment_canonical_pda(
+                student_keypair.pubkey(),
+                course_batch_address,
+            ),
             checker_data_address,
             check_result_address,
-            self.course_batch_mint_pda,
+            course_batch.mint,
             student_token_address,
             system_program::ID,
             token::ID,
             assignment_checker::ID,
             course_batch_manager::ID,
+            instructions::ID,
+            reputation::config_canonical_pda(),
+            reputation::reputation_canonical_pda(student_keypair.pubkey()),
+            reputation::writer_signer_address(&course_batch_manager::ID),
+            reputation::ID,
             [student_keypair],
         )
         .await?;
@@ -401,6 +3240,28 @@ impl Fixture {
         )
     }
 
+    /// Address of the check result of the student, batch-scoped when the scope is set
+    fn check_result_address(
+        &self,
+        student: Pubkey,
+        assignment_id: &[u8; 16],
+        scope: Option<Pubkey>,
+    ) -> Pubkey {
+        match scope {
+            Some(course_batch) => course_batch_manager::batch_check_result_canonical_pda(
+                student,
+                self.course_pda,
+                course_batch,
+                assignment_id,
+            ),
+            None => course_batch_manager::check_result_canonical_pda(
+                student,
+                self.course_pda,
+                assignment_id,
+            ),
+        }
+    }
+
     fn hash(hash_chain_length: u16, salt: &[u8; 32], value_to_hash: &[u8]) -> [u8; 32] {
         assert!(hash_chain_length >= 2);
         let first_hash = blake3::hashv(&[salt, value_to_hash]);